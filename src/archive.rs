@@ -0,0 +1,278 @@
+/// Read backups without unpacking them first.
+///
+/// Google Takeout hands out `takeout-*.zip` files with one folder per blog,
+/// each holding a `feed.atom`, and some tools store backups as `.xml.gz`.
+/// The functions here decompress on the fly and feed the xml straight into
+/// `parse_blog_with_options`.
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Cursor;
+use std::io::Read;
+use std::io::Seek;
+
+use flate2::read::GzDecoder;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use zip::ZipArchive;
+
+use crate::errors::Diagnostic;
+use crate::errors::ParseResult;
+use crate::models::Blog;
+use crate::namespaces;
+use crate::options::ParseOptions;
+use crate::parse_backup::parse_blog_with_options;
+use crate::xml_tools::read_namespaced_event;
+
+const GZIP_MAGIC: &[u8] = b"\x1f\x8b";
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const FEED_EXTENSIONS: [&str; 2] = [".atom", ".xml"];
+
+/// One blog found in an archive.
+/// A blog that fails to parse doesn't stop the others from being read.
+#[derive(Debug)]
+pub struct ArchivedBlog {
+    /// Where the feed was found: its name inside the zip, or the file path.
+    pub path: String,
+    pub result: ParseResult<(Blog, Vec<Diagnostic>)>,
+}
+
+/// Parse every blog in the zip, gzip or plain xml file at `file_path`, failing on malformed entries.
+pub fn parse_archive(file_path: &str) -> ParseResult<Vec<ArchivedBlog>> {
+    parse_archive_with_options(file_path, &ParseOptions::strict())
+}
+
+/// Like `parse_archive`, but with options.
+/// The kind of file is told from its first bytes, not its name.
+pub fn parse_archive_with_options(
+    file_path: &str,
+    options: &ParseOptions,
+) -> ParseResult<Vec<ArchivedBlog>> {
+    let mut file = BufReader::new(File::open(file_path)?);
+    let start = file.fill_buf()?;
+    if start.starts_with(ZIP_MAGIC) {
+        return parse_zip(file, options);
+    }
+    let result = if start.starts_with(GZIP_MAGIC) {
+        parse_gzip(file, options)
+    } else {
+        parse_blog_with_options(file, options)
+    };
+    Ok(vec![ArchivedBlog {
+        path: file_path.to_owned(),
+        result,
+    }])
+}
+
+/// Parse a gzipped backup such as `blog-10-31-2021.xml.gz`.
+pub fn parse_gzip<R: Read>(
+    source: R,
+    options: &ParseOptions,
+) -> ParseResult<(Blog, Vec<Diagnostic>)> {
+    parse_blog_with_options(BufReader::new(GzDecoder::new(source)), options)
+}
+
+/// Parse every blogger feed in a zip archive, in the order they are stored.
+/// Files that aren't feeds, like Takeout's `archive_browser.html`, are skipped.
+/// A file that can't be read is listed with its error,
+/// since it may have been a feed, and the rest are still read.
+pub fn parse_zip<R: Read + Seek>(
+    archive: R,
+    options: &ParseOptions,
+) -> ParseResult<Vec<ArchivedBlog>> {
+    let mut archive = ZipArchive::new(archive)?;
+    let mut blogs = Vec::new();
+    for index in 0..archive.len() {
+        let file = match archive.by_index(index) {
+            Ok(file) => file,
+            Err(error) => {
+                blogs.push(ArchivedBlog {
+                    path: format!("file {} in the archive", index + 1),
+                    result: Err(error.into()),
+                });
+                continue;
+            }
+        };
+        let path = file.name().to_owned();
+        let (name, gzipped) = match path.strip_suffix(".gz") {
+            Some(name) => (name, true),
+            None => (path.as_str(), false),
+        };
+        if file.is_dir() || !FEED_EXTENSIONS.iter().any(|ext| name.ends_with(ext)) {
+            continue;
+        }
+        let source: Box<dyn Read> = if gzipped {
+            Box::new(GzDecoder::new(file))
+        } else {
+            Box::new(file)
+        };
+        let result = match sniff_feed(source) {
+            Ok(Some(feed)) => parse_blog_with_options(feed, options),
+            Ok(None) => continue,
+            Err(error) => Err(error),
+        };
+        blogs.push(ArchivedBlog { path, result });
+    }
+    Ok(blogs)
+}
+
+/// Read up to the root element to tell a feed from a theme or other document.
+/// For a feed, returns a reader that starts from the beginning again,
+/// made of the bytes already read followed by the rest of the source.
+fn sniff_feed<R: Read>(source: R) -> ParseResult<Option<impl BufRead>> {
+    let mut reader = Reader::from_reader(BufReader::new(Recorder {
+        inner: source,
+        read: Vec::new(),
+    }));
+    let mut buf = Vec::new();
+    let mut namespace_buf = Vec::new();
+    let is_feed = loop {
+        match read_namespaced_event(&mut reader, &mut buf, &mut namespace_buf)? {
+            (Some(name), Event::Start(_)) | (Some(name), Event::Empty(_)) => {
+                break name.local == "feed"
+                    && matches!(name.namespace.as_deref(), None | Some(namespaces::ATOM));
+            }
+            (_, Event::Eof) => break false,
+            _ => buf.clear(),
+        }
+    };
+    if !is_feed {
+        return Ok(None);
+    }
+    let recorder = reader.into_underlying_reader().into_inner();
+    let replay = Cursor::new(recorder.read).chain(recorder.inner);
+    Ok(Some(BufReader::new(replay)))
+}
+
+/// Keeps a copy of everything read through it.
+struct Recorder<R> {
+    inner: R,
+    read: Vec<u8>,
+}
+
+impl<R: Read> Read for Recorder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        self.read.extend_from_slice(&buf[..count]);
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::io::Write;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use zip::write::FileOptions;
+    use zip::CompressionMethod;
+    use zip::ZipWriter;
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn zip(files: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        for (name, contents) in files {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(contents).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn takeout_zip() -> Vec<u8> {
+        let backup = std::fs::read("data/backup.xml").unwrap();
+        let feed = std::fs::read("data/feed.atom").unwrap();
+        zip(&[
+            ("Takeout/archive_browser.html", b"<html></html>".to_vec()),
+            ("Takeout/Blogger/Blogs/Field Notes/feed.atom", feed),
+            (
+                "Takeout/Blogger/Blogs/Field Notes/theme-layouts.xml",
+                b"<?xml version='1.0'?><html><body/></html>".to_vec(),
+            ),
+            (
+                "Takeout/Blogger/Blogs/Workshop/backup.xml.gz",
+                gzip(&backup),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_parse_zip() -> ParseResult<()> {
+        let blogs = parse_zip(Cursor::new(takeout_zip()), &ParseOptions::strict())?;
+        assert_eq!(blogs.len(), 2);
+        assert_eq!(blogs[0].path, "Takeout/Blogger/Blogs/Field Notes/feed.atom");
+        let (field_notes, _) = blogs[0].result.as_ref().unwrap();
+        assert_eq!(field_notes.title, "Field Notes");
+        let (workshop, _) = blogs[1].result.as_ref().unwrap();
+        assert_eq!(workshop.title, "Notes from the Workshop");
+        assert_eq!(workshop.posts.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_feeds_are_told_by_their_root_element() -> ParseResult<()> {
+        let atom = "xmlns:atom='http://www.w3.org/2005/Atom'";
+        let prefixed = format!(
+            "<?xml version='1.0'?><atom:feed {atom}><atom:title>Prefixed</atom:title></atom:feed>"
+        );
+        let long_prolog = format!(
+            "<?xml version='1.0'?><!-- {} --><feed xmlns='http://www.w3.org/2005/Atom'>\
+             <title>Long prolog</title></feed>",
+            "x".repeat(20_000)
+        );
+        let mentions_feed = "<html><body><pre>&lt;feed&gt; <!-- <feed --></pre></body></html>";
+        let other_namespace = "<feed xmlns='http://example.com/not-atom'><title>No</title></feed>";
+        let archive = zip(&[
+            ("a/feed.atom", prefixed.into_bytes()),
+            ("b/feed.xml.gz", gzip(long_prolog.as_bytes())),
+            ("c/page.xml", mentions_feed.as_bytes().to_vec()),
+            ("d/other.xml", other_namespace.as_bytes().to_vec()),
+        ]);
+        let blogs = parse_zip(Cursor::new(archive), &ParseOptions::strict())?;
+        let titles: Vec<&str> = blogs
+            .iter()
+            .map(|blog| blog.result.as_ref().unwrap().0.title.as_str())
+            .collect();
+        assert_eq!(titles, vec!["Prefixed", "Long prolog"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unreadable_files_dont_stop_the_others() -> ParseResult<()> {
+        let feed = std::fs::read("data/feed.atom")?;
+        let archive = zip(&[
+            ("a/backup.xml.gz", b"not gzip at all".to_vec()),
+            ("b/feed.atom", feed),
+        ]);
+        let blogs = parse_zip(Cursor::new(archive), &ParseOptions::strict())?;
+        assert_eq!(blogs.len(), 2);
+        assert_eq!(blogs[0].path, "a/backup.xml.gz");
+        assert!(blogs[0].result.is_err());
+        assert_eq!(blogs[1].result.as_ref().unwrap().0.title, "Field Notes");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_gzip() -> ParseResult<()> {
+        let backup = std::fs::read("data/backup.xml")?;
+        let (blog, _) = parse_gzip(&gzip(&backup)[..], &ParseOptions::strict())?;
+        assert_eq!(blog.posts.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_archive_reads_plain_xml() -> ParseResult<()> {
+        let blogs = parse_archive("data/backup.xml")?;
+        assert_eq!(blogs.len(), 1);
+        assert_eq!(blogs[0].path, "data/backup.xml");
+        assert!(blogs[0].result.is_ok());
+        Ok(())
+    }
+}
@@ -0,0 +1,1105 @@
+/// Stream the entries of a blogger backup one at a time.
+///
+/// `EntryReader` drives the quick-xml event loop and hands back each entry
+/// as soon as its closing tag is read, so callers never have to hold the
+/// whole backup in memory.  Entries come back as typed `ParsedEntry` values:
+/// posts, pages, comments, settings and templates.
+///
+/// Both the classic backup and the Google Takeout `feed.atom` are read;
+/// the format is detected from the `blogger` namespace on the feed element.
+/// Element names are resolved against their namespaces first, so files that
+/// other tools re-serialised with different prefixes read the same.
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+
+use quick_xml::events::BytesStart;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use quick_xml::Writer;
+
+use crate::errors::Diagnostic;
+use crate::errors::ParseError;
+use crate::errors::ParseResult;
+use crate::ids::PageId;
+use crate::ids::PostId;
+use crate::models::Author;
+use crate::models::ContentType;
+use crate::models::Entry;
+use crate::models::EntryKind;
+use crate::models::ExportFormat;
+use crate::models::Extension;
+use crate::models::FeedHeader;
+use crate::models::Generator;
+use crate::models::Link;
+use crate::models::ParsedEntry;
+use crate::models::PostStatus;
+use crate::namespaces;
+use crate::options::ParseOptions;
+use crate::parse_backup::parse_published;
+use crate::xml_tools::attribute_value;
+use crate::xml_tools::read_namespaced_event;
+use crate::xml_tools::string_from_bytes_text;
+use crate::xml_tools::string_from_cow;
+use crate::xml_tools::QName;
+use crate::xml_tools::XPath;
+
+const COMMENT_KIND: &[u8] = b"http://schemas.google.com/blogger/2008/kind#comment";
+const KIND_SCHEME: &str = "http://schemas.google.com/g/2005#kind";
+const LABEL_SCHEME: &str = "http://www.blogger.com/atom/ns#";
+/// The Takeout `blogger:status` of a comment that is shown on the blog.
+const LIVE_COMMENT: &str = "LIVE";
+
+/// Children of an entry that the reader understands.
+/// Names are as `QName` displays them, so `app:control` matches either app namespace.
+/// Anything else is kept in `Entry::extensions`.
+const KNOWN_ENTRY_ELEMENTS: [&str; 16] = [
+    "app:control",
+    "author",
+    "blogger:filename",
+    "blogger:inReplyTo",
+    "blogger:parent",
+    "blogger:status",
+    "blogger:type",
+    "category",
+    "content",
+    "id",
+    "link",
+    "published",
+    "thr:in-reply-to",
+    "thr:total",
+    "title",
+    "updated",
+];
+const PAGE_KIND: &[u8] = b"http://schemas.google.com/blogger/2008/kind#page";
+const POST_KIND: &[u8] = b"http://schemas.google.com/blogger/2008/kind#post";
+const SETTINGS_KIND: &[u8] = b"http://schemas.google.com/blogger/2008/kind#settings";
+const TEMPLATE_KIND: &[u8] = b"http://schemas.google.com/blogger/2008/kind#template";
+
+/// The markup inside a `type="xhtml"` title or content, copied event by event.
+struct Markup {
+    /// The depth of the title or content element.
+    depth: usize,
+    /// Whether the wrapping `<div>` atom puts around the xhtml is open.
+    in_wrapper: bool,
+    wrapper_seen: bool,
+    writer: Writer<Vec<u8>>,
+}
+
+impl Markup {
+    fn new(depth: usize) -> Self {
+        Self {
+            depth,
+            in_wrapper: false,
+            wrapper_seen: false,
+            writer: Writer::new(Vec::new()),
+        }
+    }
+}
+
+pub struct EntryReader<B: BufRead> {
+    buf: Vec<u8>,
+    diagnostics: Vec<Diagnostic>,
+    done: bool,
+    entry: Entry,
+    /// An unrecognised child of the current entry that is still being read.
+    extension: Option<Extension>,
+    feed: FeedHeader,
+    /// Inline xhtml in a title or content that is still being copied.
+    markup: Option<Markup>,
+    /// The namespace declarations in scope, kept by quick-xml between events.
+    namespace_buf: Vec<u8>,
+    options: ParseOptions,
+    reader: Reader<B>,
+    /// Posts and pages the options left out, by the post ids their comments use.
+    skipped_posts: HashSet<PostId>,
+    /// Whether the current entry has been ruled out, so its text needn't be kept.
+    skipping: bool,
+    /// Text and CDATA read so far inside the innermost open element.
+    text: String,
+    xpath: XPath,
+}
+
+impl EntryReader<BufReader<File>> {
+    pub fn from_file(file_path: &str) -> ParseResult<Self> {
+        Ok(Self::new(Reader::from_file(file_path)?))
+    }
+}
+
+impl<'a> EntryReader<&'a [u8]> {
+    pub fn from_bytes(bytes: &'a [u8]) -> Self {
+        Self::from_reader(bytes)
+    }
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(xml: &'a str) -> Self {
+        Self::from_reader(xml.as_bytes())
+    }
+}
+
+impl<B: BufRead> EntryReader<B> {
+    /// Read a backup from anything buffered: an open file, an upload, a zip entry...
+    pub fn from_reader(source: B) -> Self {
+        Self::new(Reader::from_reader(source))
+    }
+
+    fn new(reader: Reader<B>) -> Self {
+        Self {
+            buf: Vec::new(),
+            diagnostics: Vec::new(),
+            done: false,
+            entry: Entry::new(),
+            extension: None,
+            feed: FeedHeader::default(),
+            markup: None,
+            namespace_buf: Vec::new(),
+            options: ParseOptions::default(),
+            reader,
+            skipped_posts: HashSet::new(),
+            skipping: false,
+            text: String::new(),
+            xpath: XPath::new(),
+        }
+    }
+
+    /// The blog's own id, title, author and so on.
+    /// Blogger writes these before the first entry,
+    /// so they are filled in by the time an entry comes back.
+    pub fn feed(&self) -> &FeedHeader {
+        &self.feed
+    }
+
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Problems found so far.  Lenient parses report what they worked around,
+    /// and entries skipped for their size, or comments that aren't live,
+    /// are reported in either mode.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// The byte offset reached so far.  Right after `next_entry`
+    /// it is where the entry that came back ends.
+    pub fn position(&self) -> usize {
+        self.reader.buffer_position()
+    }
+
+    /// The posts and pages left out by the options so far,
+    /// as the post ids their comments use, so those comments can be left out too.
+    pub fn skipped_posts(&self) -> &HashSet<PostId> {
+        &self.skipped_posts
+    }
+
+    /// Read events until the next entry of a known kind is complete.
+    /// Returns `None` once the end of the document is reached.
+    pub fn next_entry(&mut self) -> ParseResult<Option<ParsedEntry>> {
+        let mut buf = std::mem::take(&mut self.buf);
+        let mut namespace_buf = std::mem::take(&mut self.namespace_buf);
+        let next = self.read_entry(&mut buf, &mut namespace_buf);
+        self.buf = buf;
+        self.namespace_buf = namespace_buf;
+        next.map_err(|e| e.located(self.reader.buffer_position(), self.entry.id.as_deref()))
+    }
+
+    fn read_entry(
+        &mut self,
+        buf: &mut Vec<u8>,
+        namespace_buf: &mut Vec<u8>,
+    ) -> ParseResult<Option<ParsedEntry>> {
+        loop {
+            buf.clear();
+            let event = read_namespaced_event(&mut self.reader, buf, namespace_buf);
+            if let Ok((name, event)) = &event {
+                if self.copy_markup(name, event)? {
+                    continue;
+                }
+            }
+            match event {
+                Ok((Some(name), Event::Start(ref bytes_start))) => {
+                    if self.xpath.as_string() == "feed=>entry" {
+                        self.check_rejected();
+                    }
+                    let unknown = (!is_known_entry_element(&name)).then(|| name.clone());
+                    self.xpath.push(name);
+                    self.text.clear();
+                    match self.xpath.as_string().as_str() {
+                        "feed" => {
+                            let takeout = bytes_start.attributes().flatten().any(|attribute| {
+                                attribute.value.as_ref() == namespaces::BLOGGER.as_bytes()
+                            });
+                            if takeout {
+                                self.feed.format = ExportFormat::Takeout;
+                            }
+                        }
+                        "feed=>author" => self.feed.author = Some(Author::default()),
+                        "feed=>generator" => {
+                            self.feed.generator = Some(Generator {
+                                name: String::new(),
+                                uri: attribute_value(bytes_start, b"uri")?,
+                                version: attribute_value(bytes_start, b"version")?,
+                            })
+                        }
+                        "feed=>entry=>author" => self.entry.author = Some(Author::default()),
+                        "feed=>entry=>thr:in-reply-to" => self.set_parent_post(bytes_start)?,
+                        "feed=>entry=>title" => {
+                            self.entry.title_type = content_type_of(bytes_start)?;
+                            if self.entry.title_type == ContentType::Xhtml && !self.skipping {
+                                self.markup = Some(Markup::new(self.xpath.len()));
+                            }
+                        }
+                        "feed=>entry=>content" => {
+                            self.entry.content_type = content_type_of(bytes_start)?;
+                            if self.entry.content_type == ContentType::Xhtml && !self.skipping {
+                                self.markup = Some(Markup::new(self.xpath.len()));
+                            }
+                        }
+                        _ => (),
+                    }
+                    if let Some(name) = unknown {
+                        if self.xpath.len() == 3
+                            && self.xpath.as_string().starts_with("feed=>entry=>")
+                        {
+                            self.extension = Some(extension_from_element(name, bytes_start)?);
+                        }
+                    }
+                }
+                Ok((Some(name), Event::End(_))) => {
+                    let text = std::mem::take(&mut self.text);
+                    self.assign_text(text)?;
+                    if self.xpath.len() == 3 {
+                        if let Some(extension) = self.extension.take() {
+                            self.entry.add_extension(extension);
+                        }
+                    }
+                    let parsed = if self.xpath.as_string() == "feed=>entry" {
+                        let parsed = self.finish_entry()?;
+                        self.entry.clear();
+                        self.skipping = false;
+                        parsed
+                    } else {
+                        None
+                    };
+                    self.xpath.pop_checked(name)?;
+                    if parsed.is_some() {
+                        return Ok(parsed);
+                    }
+                }
+                Ok((Some(name), Event::Empty(byte_start))) => {
+                    if name.to_string() == "gd:image" {
+                        let src = attribute_value(&byte_start, b"src")?;
+                        if let Some(author) = self.current_author() {
+                            author.image_url = src.map(|src| match src.strip_prefix("//") {
+                                Some(rest) => format!("https://{}", rest),
+                                None => src,
+                            });
+                        }
+                    }
+                    if self.xpath.as_string() == "feed=>entry" {
+                        match name.to_string().as_str() {
+                            "category" => {
+                                let scheme = attribute_value(&byte_start, b"scheme")?;
+                                let term = attribute_value(&byte_start, b"term")?;
+                                match (scheme.as_deref(), term) {
+                                    (Some(LABEL_SCHEME), Some(label)) => {
+                                        self.entry.labels.push(label)
+                                    }
+                                    (Some(KIND_SCHEME), Some(term)) => self.set_kind(term),
+                                    _ => (),
+                                }
+                            }
+                            "link" => {
+                                if let Some(link) = link_from_element(&byte_start)? {
+                                    self.entry.links.push(link);
+                                }
+                            }
+                            "title" => {
+                                self.entry.title = Some(String::new());
+                                self.entry.title_type = content_type_of(&byte_start)?;
+                            }
+                            "content" => {
+                                self.entry.content = Some(String::new());
+                                self.entry.content_type = content_type_of(&byte_start)?;
+                            }
+                            "thr:in-reply-to" => self.set_parent_post(&byte_start)?,
+                            _ if !is_known_entry_element(&name) => {
+                                let extension = extension_from_element(name, &byte_start)?;
+                                self.entry.add_extension(extension);
+                            }
+                            _ => (),
+                        }
+                        self.check_rejected();
+                    }
+                }
+                Ok((_, Event::Text(bytes_text))) => {
+                    let text = string_from_bytes_text(bytes_text);
+                    self.push_text(text)?;
+                }
+                Ok((_, Event::CData(bytes_cdata))) => {
+                    let text = string_from_bytes_text(bytes_cdata);
+                    self.push_text(text)?;
+                }
+                Ok((_, Event::Eof)) if self.xpath.is_empty() => return Ok(None),
+                Ok((_, Event::Eof)) => {
+                    return Err(ParseError::UnexpectedEof {
+                        position: 0,
+                        entry_id: None,
+                        open: self.xpath.as_string(),
+                    })
+                }
+                Err(e) => return Err(e),
+                Ok(_event) => {}
+            }
+        }
+    }
+}
+
+impl<B: BufRead> Iterator for EntryReader<B> {
+    type Item = ParseResult<ParsedEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_entry().transpose();
+        if !matches!(next, Some(Ok(_))) {
+            self.done = true;
+        }
+        next
+    }
+}
+
+impl<B: BufRead> EntryReader<B> {
+    /// In strict mode hand the error back; otherwise note it and carry on.
+    fn recover(&mut self, error: ParseError) -> ParseResult<()> {
+        let error = error.located(self.reader.buffer_position(), self.entry.id.as_deref());
+        if self.options.strict {
+            return Err(error);
+        }
+        let diagnostic = Diagnostic::from_error(&error, self.xpath.as_string());
+        self.diagnostics.push(diagnostic);
+        Ok(())
+    }
+
+    /// While inside inline xhtml, copy each event into the markup instead of parsing it.
+    /// Returns false for events the main loop should handle,
+    /// including the end of the title or content, whose text is then the markup.
+    fn copy_markup(&mut self, name: &Option<QName>, event: &Event) -> ParseResult<bool> {
+        let markup = match self.markup.as_mut() {
+            Some(markup) => markup,
+            None => return Ok(false),
+        };
+        let depth = self.xpath.len();
+        match (name, event) {
+            (Some(name), Event::Start(_)) => {
+                let only_whitespace = markup.writer.inner().iter().all(u8::is_ascii_whitespace);
+                if depth == markup.depth
+                    && !markup.wrapper_seen
+                    && only_whitespace
+                    && name.is(namespaces::XHTML, "div")
+                {
+                    markup.in_wrapper = true;
+                    markup.wrapper_seen = true;
+                } else {
+                    markup.writer.write_event(event)?;
+                }
+                self.xpath.push(name.to_owned());
+            }
+            (Some(_), Event::End(_)) if depth == markup.depth => {
+                let markup = self.markup.take().map(|markup| markup.writer.into_inner());
+                self.text = String::from_utf8(markup.unwrap_or_default())?;
+                return Ok(false);
+            }
+            (Some(name), Event::End(_)) => {
+                if depth == markup.depth + 1 && markup.in_wrapper {
+                    markup.in_wrapper = false;
+                } else {
+                    markup.writer.write_event(event)?;
+                }
+                self.xpath.pop_checked(name.to_owned())?;
+            }
+            (_, Event::Eof) => return Ok(false),
+            // Indentation around the wrapping div isn't part of the content.
+            (_, Event::Text(text))
+                if depth == markup.depth && text.iter().all(u8::is_ascii_whitespace) => {}
+            // quick-xml hands CDATA over escaped and would write it back that way,
+            // so put the section back together from the original text.
+            (_, Event::CData(cdata)) => {
+                let text = cdata.unescaped()?;
+                markup.writer.write(b"<![CDATA[")?;
+                markup.writer.write(&text)?;
+                markup.writer.write(b"]]>")?;
+            }
+            (_, event) => markup.writer.write_event(event)?,
+        }
+        let size = self.markup.as_mut().map_or(0, |m| m.writer.inner().len());
+        if self.is_over_max_content_size(size) {
+            self.skip_oversized();
+        }
+        Ok(true)
+    }
+
+    /// Add a piece of an element's text, which may come in several parts.
+    fn push_text(&mut self, text: ParseResult<String>) -> ParseResult<()> {
+        let text = match text {
+            Ok(text) => text,
+            Err(error) => return self.recover(error),
+        };
+        if !self.keeps_text() {
+            return Ok(());
+        }
+        match self.extension.as_mut() {
+            Some(extension) => extension.text.push_str(&text),
+            None => self.text.push_str(&text),
+        }
+        if self.is_over_max_content_size(self.text.len()) {
+            self.skip_oversized();
+        }
+        Ok(())
+    }
+
+    /// Rule out the current entry as soon as what has been read of it doesn't match the options.
+    fn check_rejected(&mut self) {
+        if !self.skipping && self.options.rejects(&self.entry) {
+            self.skipping = true;
+        }
+    }
+
+    /// A skipped entry only needs its id and kind, to tell comments on it from orphans.
+    fn keeps_text(&self) -> bool {
+        !self.skipping
+            || matches!(
+                self.xpath.as_string().as_str(),
+                "feed=>entry=>id" | "feed=>entry=>blogger:type"
+            )
+    }
+
+    /// Whether the content being read has grown past `ParseOptions::max_content_size`.
+    fn is_over_max_content_size(&self, size: usize) -> bool {
+        match self.options.max_content_size {
+            Some(max) => size > max && self.xpath.as_string().starts_with("feed=>entry=>content"),
+            None => false,
+        }
+    }
+
+    /// Drop the content read so far and the rest of the entry with it.
+    fn skip_oversized(&mut self) {
+        self.skipping = true;
+        self.markup = None;
+        self.text.clear();
+        let max = self.options.max_content_size.unwrap_or_default();
+        self.diagnostics.push(Diagnostic {
+            entry_id: self.entry.id.to_owned(),
+            position: self.reader.buffer_position(),
+            xpath: self.xpath.as_string(),
+            reason: format!("content is over {} bytes, skipped the entry", max),
+        });
+    }
+
+    /// Store the complete text of the element that is closing.
+    fn assign_text(&mut self, text: String) -> ParseResult<()> {
+        if !self.keeps_text() {
+            return Ok(());
+        }
+        match self.xpath.as_string().as_str() {
+            "feed=>id" => self.feed.id = Some(text),
+            "feed=>title" => self.feed.title = Some(text),
+            "feed=>updated" => match parse_published(&text) {
+                Ok(updated) => self.feed.updated = Some(updated),
+                Err(error) => self.recover(error)?,
+            },
+            "feed=>generator" => {
+                if let Some(generator) = self.feed.generator.as_mut() {
+                    generator.name = text;
+                }
+            }
+            "feed=>author=>name" | "feed=>entry=>author=>name" => {
+                if let Some(author) = self.current_author() {
+                    author.name = text;
+                }
+            }
+            "feed=>author=>email" | "feed=>entry=>author=>email" => {
+                if let Some(author) = self.current_author() {
+                    author.email = Some(text);
+                }
+            }
+            "feed=>author=>blogger:type" | "feed=>entry=>author=>blogger:type" => {
+                if let Some(author) = self.current_author() {
+                    author.author_type = Some(text.trim().to_owned());
+                }
+            }
+            "feed=>author=>uri" | "feed=>entry=>author=>uri" => {
+                if let Some(author) = self.current_author() {
+                    author.uri = Some(text);
+                }
+            }
+            "feed=>entry=>published" => match parse_published(&text) {
+                Ok(published) => self.entry.published = Some(published),
+                Err(error) => self.recover(error)?,
+            },
+            "feed=>entry=>updated" => match parse_published(&text) {
+                Ok(updated) => self.entry.updated = Some(updated),
+                Err(error) => self.recover(error)?,
+            },
+            "feed=>entry=>thr:total" => match text.trim().parse() {
+                Ok(total) => self.entry.declared_comment_count = Some(total),
+                Err(_) => self.recover(ParseError::BadValue {
+                    position: 0,
+                    entry_id: None,
+                    field: "thr:total",
+                    value: text,
+                })?,
+            },
+            "feed=>entry=>id" => self.entry.id = Some(text),
+            "feed=>entry=>title" => self.entry.title = Some(text),
+            "feed=>entry=>content" => self.entry.content = Some(text),
+            "feed=>entry=>blogger:type" => self.set_takeout_kind(text),
+            "feed=>entry=>blogger:status" => self.entry.takeout_status = Some(text),
+            "feed=>entry=>blogger:filename" if !text.is_empty() => self.entry.filename = Some(text),
+            "feed=>entry=>blogger:parent" => self.entry.post_id = Some(text),
+            "feed=>entry=>blogger:inReplyTo" => self.entry.in_reply_to = Some(text),
+            "feed=>entry=>app:control=>app:draft" if text == "yes" => {
+                self.entry.status = PostStatus::Draft
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Set the kind from a `kind#...` category term.
+    /// Unknown kinds are kept, but don't override a kind that is already known.
+    fn set_kind(&mut self, term: String) {
+        let kind = match term.as_bytes() {
+            COMMENT_KIND => EntryKind::Comment,
+            PAGE_KIND => EntryKind::Page,
+            POST_KIND => EntryKind::Post,
+            SETTINGS_KIND => EntryKind::Settings,
+            TEMPLATE_KIND => EntryKind::Template,
+            _ if self.entry.kind.is_some() => return,
+            _ => EntryKind::Other(term),
+        };
+        self.entry.kind = Some(kind);
+    }
+
+    /// A comment names the post it is on with `ref` in `thr:in-reply-to`.
+    /// The `href` and `source` there point at the post's page and feed instead.
+    fn set_parent_post(&mut self, in_reply_to: &BytesStart) -> ParseResult<()> {
+        if let Some(post_id) = attribute_value(in_reply_to, b"ref")? {
+            self.entry.post_id = Some(post_id);
+        }
+        Ok(())
+    }
+
+    /// Set the kind from a Takeout `blogger:type`.
+    fn set_takeout_kind(&mut self, blogger_type: String) {
+        self.feed.format = ExportFormat::Takeout;
+        self.entry.kind = Some(match blogger_type.as_str() {
+            "COMMENT" => EntryKind::Comment,
+            "PAGE" => EntryKind::Page,
+            "POST" => EntryKind::Post,
+            _ => EntryKind::Other(blogger_type),
+        });
+    }
+
+    /// The author element currently being read, if any.
+    fn current_author(&mut self) -> Option<&mut Author> {
+        let xpath = self.xpath.as_string();
+        if xpath.starts_with("feed=>entry=>author") {
+            self.entry.author.as_mut()
+        } else if xpath.starts_with("feed=>author") {
+            self.feed.author.as_mut()
+        } else {
+            None
+        }
+    }
+
+    fn finish_entry(&mut self) -> ParseResult<Option<ParsedEntry>> {
+        let takeout_status = self.entry.takeout_status.take();
+        if let Some(status) = &takeout_status {
+            if matches!(self.entry.kind, Some(EntryKind::Post | EntryKind::Page)) {
+                self.set_post_status(status)?;
+            }
+        }
+        if self.is_scheduled() {
+            self.entry.status = PostStatus::Scheduled;
+        }
+        if self.skipping || !self.options.accepts(&self.entry) {
+            self.note_skipped();
+            return Ok(None);
+        }
+        if let Some(status) = takeout_status {
+            if self.entry.kind == Some(EntryKind::Comment) && status != LIVE_COMMENT {
+                self.skip_hidden_comment(&status);
+                return Ok(None);
+            }
+        }
+        if !self.options.strict {
+            self.fill_missing();
+        }
+        match to_parsed_entry(&self.entry) {
+            Ok(parsed) => Ok(parsed),
+            Err(error) => {
+                self.recover(error)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Read a post or page's Takeout `blogger:status`.
+    fn set_post_status(&mut self, value: &str) -> ParseResult<()> {
+        match PostStatus::from_takeout(value) {
+            Some(status) => self.entry.status = status,
+            None => {
+                // Keep whatever this is from being published by mistake.
+                self.entry.status = PostStatus::Draft;
+                self.recover(ParseError::BadValue {
+                    position: 0,
+                    entry_id: None,
+                    field: "blogger:status",
+                    value: value.to_owned(),
+                })?
+            }
+        }
+        Ok(())
+    }
+
+    /// Leave out a comment that isn't shown on the blog, such as one held for moderation.
+    fn skip_hidden_comment(&mut self, status: &str) {
+        self.diagnostics.push(Diagnostic {
+            entry_id: self.entry.id.to_owned(),
+            position: self.reader.buffer_position(),
+            xpath: self.xpath.as_string(),
+            reason: format!("comment status is {}, skipped the entry", status),
+        });
+    }
+
+    /// Remember a skipped post or page, so that comments on it aren't taken for orphans.
+    fn note_skipped(&mut self) {
+        let id = self.entry.id.as_deref().unwrap_or_default();
+        let post_id = match self.entry.kind {
+            Some(EntryKind::Post) => PostId::parse(id),
+            Some(EntryKind::Page) => PageId::parse(id).map(|page| page.as_post_id()),
+            _ => None,
+        };
+        self.skipped_posts.extend(post_id);
+    }
+
+    /// Blogger exports scheduled posts with a publish date after the backup was made.
+    /// Takeout feeds have no `updated`, so there only `blogger:status` can say so.
+    fn is_scheduled(&self) -> bool {
+        let (published, backed_up) = match (self.entry.published, self.feed.updated) {
+            (Some(published), Some(backed_up)) => (published, backed_up),
+            _ => return false,
+        };
+        matches!(self.entry.status, PostStatus::Live | PostStatus::Draft)
+            && matches!(
+                self.entry.kind,
+                Some(EntryKind::Post) | Some(EntryKind::Page)
+            )
+            && published > backed_up
+    }
+
+    /// Leniently patch up posts and comments that lack text fields or an update time,
+    /// leaving the text empty and using the published time rather than dropping the whole entry.
+    fn fill_missing(&mut self) {
+        if !matches!(
+            self.entry.kind,
+            Some(EntryKind::Post) | Some(EntryKind::Page) | Some(EntryKind::Comment)
+        ) {
+            return;
+        }
+        let mut filled = Vec::new();
+        if self.entry.author.is_none() {
+            self.entry.author = Some(Author::default());
+            filled.push("author");
+        }
+        for (field, value) in [
+            ("content", &mut self.entry.content),
+            ("title", &mut self.entry.title),
+        ] {
+            if value.is_none() {
+                *value = Some(String::new());
+                filled.push(field);
+            }
+        }
+        if self.entry.updated.is_none() {
+            self.entry.updated = self.entry.published;
+            filled.push("updated time");
+        }
+        for field in filled {
+            self.diagnostics.push(Diagnostic {
+                entry_id: self.entry.id.to_owned(),
+                position: self.reader.buffer_position(),
+                xpath: self.xpath.as_string(),
+                reason: format!("entry has no {}, filled it in", field),
+            });
+        }
+    }
+}
+
+fn content_type_of(bytes_start: &BytesStart) -> ParseResult<ContentType> {
+    let value = attribute_value(bytes_start, b"type")?;
+    Ok(ContentType::from_attribute(value.as_deref()))
+}
+
+fn is_known_entry_element(name: &QName) -> bool {
+    KNOWN_ENTRY_ELEMENTS.contains(&name.to_string().as_str())
+}
+
+fn extension_from_element(name: QName, bytes_start: &BytesStart) -> ParseResult<Extension> {
+    let mut attributes = Vec::new();
+    for attribute in bytes_start.attributes() {
+        let attribute = attribute?;
+        let key = string_from_cow(attribute.key.into())?;
+        let value = string_from_cow(attribute.unescaped_value()?)?;
+        attributes.push((key, value));
+    }
+    Ok(Extension {
+        attributes,
+        name: name.to_string(),
+        namespace: name.namespace,
+        text: String::new(),
+    })
+}
+
+/// Links without a rel or href aren't useful to anyone, so they're dropped.
+fn link_from_element(bytes_start: &BytesStart) -> ParseResult<Option<Link>> {
+    let rel = attribute_value(bytes_start, b"rel")?;
+    let href = attribute_value(bytes_start, b"href")?;
+    if let (Some(rel), Some(href)) = (rel, href) {
+        Ok(Some(Link {
+            href,
+            media_type: attribute_value(bytes_start, b"type")?,
+            rel,
+            title: attribute_value(bytes_start, b"title")?,
+        }))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Convert a completed entry into its typed form.
+/// Entries of a kind we don't model come back as they are,
+/// and entries without any kind are skipped.
+fn to_parsed_entry(entry: &Entry) -> ParseResult<Option<ParsedEntry>> {
+    let parsed = match entry.kind {
+        Some(EntryKind::Comment) => ParsedEntry::Comment(entry.to_comment()?),
+        Some(EntryKind::Page) => ParsedEntry::Page(entry.to_page()?),
+        Some(EntryKind::Post) => ParsedEntry::Post(entry.to_post()?),
+        Some(EntryKind::Settings) => ParsedEntry::Settings(entry.to_setting()?),
+        Some(EntryKind::Template) => ParsedEntry::Template(entry.to_template()?),
+        Some(EntryKind::Other(_)) => ParsedEntry::Other(entry.to_owned()),
+        None => return Ok(None),
+    };
+    Ok(Some(parsed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Post;
+
+    #[test]
+    fn test_authors() -> ParseResult<()> {
+        let mut entries = EntryReader::from_file("data/backup.xml")?;
+        let mut comment_authors = Vec::new();
+        for entry in &mut entries {
+            if let ParsedEntry::Comment(comment) = entry? {
+                comment_authors.push(comment.author);
+            }
+        }
+        let feed = entries.feed();
+        assert_eq!(feed.title.as_deref(), Some("Notes from the Workshop"));
+        let feed_author = feed.author.as_ref().unwrap();
+        assert_eq!(feed_author.name, "Dana Harris");
+        assert_eq!(
+            feed_author.uri.as_deref(),
+            Some("https://www.blogger.com/profile/08123456789012345678")
+        );
+        assert_eq!(
+            feed_author.image_url.as_deref(),
+            Some("https://blogger.googleusercontent.com/img/b/avatar/dana.jpg")
+        );
+        assert!(feed_author.has_placeholder_email());
+        assert_eq!(feed_author.real_email(), None);
+
+        let marco = &comment_authors[0];
+        assert_eq!(marco.name, "Marco Ruiz");
+        assert!(!marco.is_anonymous());
+        assert_eq!(marco.real_image_url(), None);
+        let anonymous = &comment_authors[2];
+        assert!(anonymous.is_anonymous());
+        assert_eq!(anonymous.uri, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_kinds_and_extensions() -> ParseResult<()> {
+        let xml = "<feed><entry><id>tag:blogger.com,1999:blog-1.gadget-7</id>\
+            <category scheme='http://schemas.google.com/g/2005#kind' \
+             term='http://schemas.google.com/blogger/2021/kind#gadget'/>\
+            <georss:point>40.0 -105.2</georss:point>\
+            <media:thumbnail url='https://example.org/t.jpg' width='72'/>\
+            <blogger:extra><blogger:inner>a</blogger:inner>b</blogger:extra>\
+            </entry></feed>";
+        let entries: Vec<ParsedEntry> = EntryReader::from_str(xml).collect::<ParseResult<_>>()?;
+        assert_eq!(entries.len(), 1);
+        let entry = match &entries[0] {
+            ParsedEntry::Other(entry) => entry,
+            other => panic!("expected an unknown entry, got {:?}", other),
+        };
+        assert_eq!(
+            entry.kind,
+            Some(EntryKind::Other(
+                "http://schemas.google.com/blogger/2021/kind#gadget".to_owned()
+            ))
+        );
+        assert_eq!(entry.extension("georss:point").unwrap().text, "40.0 -105.2");
+        let thumbnail = entry.extension("media:thumbnail").unwrap();
+        assert_eq!(
+            thumbnail.attribute("url"),
+            Some("https://example.org/t.jpg")
+        );
+        assert_eq!(entry.extension("blogger:extra").unwrap().text, "ab");
+        Ok(())
+    }
+
+    #[test]
+    fn test_post_extensions() -> ParseResult<()> {
+        for entry in EntryReader::from_file("data/backup.xml")? {
+            match entry? {
+                ParsedEntry::Post(post) if post.title.starts_with("A crosscut") => {
+                    assert!(post.extensions.contains_key("media:thumbnail"));
+                }
+                ParsedEntry::Comment(comment) => {
+                    assert_eq!(comment.extensions["gd:extendedProperty"].len(), 2);
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn only_post(xml: &str) -> ParseResult<Post> {
+        let entries: Vec<ParsedEntry> = EntryReader::from_str(xml).collect::<ParseResult<_>>()?;
+        match entries.into_iter().next() {
+            Some(ParsedEntry::Post(post)) => Ok(post),
+            other => panic!("expected a post, got {:?}", other),
+        }
+    }
+
+    fn post_xml(title: &str, content: &str) -> String {
+        format!(
+            "<feed><entry><id>tag:blogger.com,1999:blog-1.post-2</id>\
+             <published>2020-01-01T00:00:00Z</published><updated>2020-01-01T00:00:00Z</updated>\
+             <category scheme='http://schemas.google.com/g/2005#kind' \
+              term='http://schemas.google.com/blogger/2008/kind#post'/>\
+             <author><name>A</name></author>\
+             <title>{}</title><content type='html'>{}</content></entry></feed>",
+            title, content
+        )
+    }
+
+    #[test]
+    fn test_xhtml_content() -> ParseResult<()> {
+        let xml = post_xml("a &lt; b", "X").replace(
+            "<content type='html'>X</content>",
+            "<content type='xhtml'>\n  <div xmlns='http://www.w3.org/1999/xhtml'>\
+             <p>One <em>two</em> &amp; three</p><br/></div>\n</content>",
+        );
+        let post = only_post(&xml)?;
+        assert_eq!(post.title, "a < b");
+        assert_eq!(post.title_type, ContentType::Text);
+        assert!(post.title_type.needs_escaping());
+        assert_eq!(post.content_type, ContentType::Xhtml);
+        assert_eq!(post.content, "<p>One <em>two</em> &amp; three</p><br/>");
+        Ok(())
+    }
+
+    #[test]
+    fn test_cdata_in_xhtml_content() -> ParseResult<()> {
+        let xml = post_xml("X", "X").replace(
+            "<content type='html'>X</content>",
+            "<content type='xhtml'><div xmlns='http://www.w3.org/1999/xhtml'>\
+             <p><![CDATA[a & b < c]]></p></div></content>",
+        );
+        let post = only_post(&xml)?;
+        assert_eq!(post.content, "<p><![CDATA[a & b < c]]></p>");
+        Ok(())
+    }
+
+    #[test]
+    fn test_xhtml_title() -> ParseResult<()> {
+        let xml = post_xml("X", "Body").replace(
+            "<title>X</title>",
+            "<title type='xhtml'><div xmlns='http://www.w3.org/1999/xhtml'>\
+             Fish <b>&amp;</b> chips</div></title>",
+        );
+        let post = only_post(&xml)?;
+        assert_eq!(post.title_type, ContentType::Xhtml);
+        assert_eq!(post.title, "Fish <b>&amp;</b> chips");
+        assert_eq!(post.content_type, ContentType::Html);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cdata_content() -> ParseResult<()> {
+        let post = only_post(&post_xml(
+            "<![CDATA[Fish & chips]]>",
+            "<![CDATA[<p>Hello <b>world</b></p>]]>",
+        ))?;
+        assert_eq!(post.title, "Fish & chips");
+        assert_eq!(post.content, "<p>Hello <b>world</b></p>");
+        Ok(())
+    }
+
+    #[test]
+    fn test_text_in_several_parts() -> ParseResult<()> {
+        let post = only_post(&post_xml(
+            "Part one<!-- a comment -->, part two",
+            "&lt;p&gt;Before<![CDATA[ <i>middle</i> ]]>after&lt;/p&gt;",
+        ))?;
+        assert_eq!(post.title, "Part one, part two");
+        assert_eq!(post.content, "<p>Before <i>middle</i> after</p>");
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefixes_other_tools_chose() -> ParseResult<()> {
+        let xml = "<atom:feed xmlns:atom='http://www.w3.org/2005/Atom' \
+            xmlns:t='http://purl.org/syndication/thread/1.0' \
+            xmlns:pub='http://www.w3.org/2007/app'>\
+            <atom:entry><atom:id>tag:blogger.com,1999:blog-1.post-2</atom:id>\
+            <atom:published>2020-01-01T00:00:00Z</atom:published>\
+            <atom:updated>2020-01-02T00:00:00Z</atom:updated>\
+            <atom:category scheme='http://schemas.google.com/g/2005#kind' \
+             term='http://schemas.google.com/blogger/2008/kind#post'/>\
+            <atom:title>Renamed</atom:title><atom:content>Body</atom:content>\
+            <atom:author><atom:name>Someone</atom:name></atom:author>\
+            <pub:control><pub:draft>yes</pub:draft></pub:control>\
+            <count xmlns='http://purl.org/syndication/thread/1.0'>9</count>\
+            <t:total>4</t:total></atom:entry></atom:feed>";
+        let entries: Vec<ParsedEntry> = EntryReader::from_str(xml).collect::<ParseResult<_>>()?;
+        match &entries[..] {
+            [ParsedEntry::Post(post)] => {
+                assert_eq!(post.title, "Renamed");
+                assert_eq!(post.author.name, "Someone");
+                assert_eq!(post.status, PostStatus::Draft);
+                assert_eq!(post.declared_comment_count, Some(4));
+                let count = &post.extensions["thr:count"][0];
+                assert_eq!(count.namespace.as_deref(), Some(namespaces::THR));
+            }
+            other => panic!("expected one post, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_scheduled_and_trashed_posts() -> ParseResult<()> {
+        let future = post_xml("Later", "Soon").replace(
+            "<feed><entry>",
+            "<feed><updated>2019-06-01T00:00:00Z</updated><entry>",
+        );
+        assert_eq!(only_post(&future)?.status, PostStatus::Scheduled);
+
+        let trashed = post_xml("Gone", "Bye").replace(
+            "<author>",
+            "<blogger:status xmlns:blogger='http://schemas.google.com/blogger/2018'>\
+             SOFT_TRASHED</blogger:status><author>",
+        );
+        assert_eq!(only_post(&trashed)?.status, PostStatus::SoftTrashed);
+
+        let unknown = trashed.replace("SOFT_TRASHED", "ARCHIVED");
+        assert!(matches!(
+            only_post(&unknown),
+            Err(ParseError::BadValue {
+                field: "blogger:status",
+                ..
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_takeout_entries() -> ParseResult<()> {
+        let mut reader = EntryReader::from_file("data/feed.atom")?;
+        let entries: Vec<ParsedEntry> = (&mut reader).collect::<ParseResult<_>>()?;
+        assert_eq!(reader.feed().format, ExportFormat::Takeout);
+        assert_eq!(entries.len(), 6);
+        // The comment held for moderation is left out.
+        let diagnostics = reader.diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].entry_id.as_deref(),
+            Some("tag:blogger.com,1999:blog-7700112233445566778.post-8080808080808080804")
+        );
+        assert_eq!(
+            diagnostics[0].reason,
+            "comment status is PENDING, skipped the entry"
+        );
+        match &entries[0] {
+            ParsedEntry::Post(post) => {
+                assert_eq!(post.labels, vec!["Birds", "Counts"]);
+                assert_eq!(post.status, PostStatus::Live);
+                assert!(post.extensions.contains_key("blogger:location"));
+            }
+            other => panic!("expected a post, got {:?}", other),
+        }
+        match &entries[1] {
+            ParsedEntry::Post(post) => {
+                assert_eq!(post.status, PostStatus::Draft);
+                assert_eq!(post.filename, None);
+            }
+            other => panic!("expected a post, got {:?}", other),
+        }
+        match &entries[4] {
+            ParsedEntry::Comment(comment) => {
+                assert_eq!(comment.post_id.number(), 1029384756102938475);
+                assert_eq!(comment.in_reply_to.unwrap().number(), 8080808080808080801);
+                assert_eq!(comment.title, "");
+                assert_eq!(comment.author.author_type.as_deref(), Some("BLOGGER"));
+                assert!(!comment.author.is_anonymous());
+            }
+            other => panic!("expected a comment, got {:?}", other),
+        }
+        match &entries[3] {
+            ParsedEntry::Comment(comment) => {
+                assert_eq!(comment.author.name, "Lena Park");
+                assert!(comment.author.is_anonymous());
+            }
+            other => panic!("expected a comment, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_entry_reader() -> ParseResult<()> {
+        let mut posts = 0;
+        let mut pages = 0;
+        let mut comments = 0;
+        let mut settings = 0;
+        let mut templates = 0;
+        for entry in EntryReader::from_file("data/backup.xml")? {
+            match entry? {
+                ParsedEntry::Comment(_) => comments += 1,
+                ParsedEntry::Page(_) => pages += 1,
+                ParsedEntry::Post(_) => posts += 1,
+                ParsedEntry::Settings(_) => settings += 1,
+                ParsedEntry::Template(_) => templates += 1,
+                ParsedEntry::Other(entry) => panic!("unexpected entry {:?}", entry),
+            }
+        }
+        assert_eq!(posts, 3);
+        assert_eq!(pages, 1);
+        assert_eq!(comments, 5);
+        assert_eq!(settings, 7);
+        assert_eq!(templates, 1);
+        Ok(())
+    }
+}
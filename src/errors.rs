@@ -1,4 +1,7 @@
+use std::fmt;
 use std::fmt::Debug;
+use std::io;
+use std::string::FromUtf8Error;
 
 pub type BtError = Box<dyn std::error::Error>;
 pub type BtResult<T> = Result<T, BtError>;
@@ -7,3 +10,268 @@ pub type EmptyResult = Result<(), BtError>;
 pub fn errstr<T: Debug>(message: &str, error: T) -> String {
     format!("{} Error is `{:?}`", message, error)
 }
+
+pub type ParseResult<T> = Result<T, ParseError>;
+
+/// Everything that can go wrong while reading a backup.
+///
+/// `position` is the byte offset the reader had reached when the problem was found
+/// and `entry_id` is the id of the entry being read, when there was one.
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    Zip(zip::result::ZipError),
+    Xml {
+        position: usize,
+        entry_id: Option<String>,
+        source: quick_xml::Error,
+    },
+    Utf8 {
+        position: usize,
+        entry_id: Option<String>,
+        source: FromUtf8Error,
+    },
+    BadTimestamp {
+        position: usize,
+        entry_id: Option<String>,
+        value: String,
+        source: chrono::ParseError,
+    },
+    MissingField {
+        position: usize,
+        entry_id: Option<String>,
+        field: &'static str,
+    },
+    UnexpectedKind {
+        position: usize,
+        entry_id: Option<String>,
+        expected: &'static str,
+    },
+    BadValue {
+        position: usize,
+        entry_id: Option<String>,
+        field: &'static str,
+        value: String,
+    },
+    MismatchedTag {
+        position: usize,
+        entry_id: Option<String>,
+        expected: String,
+        found: String,
+    },
+    UnexpectedEnd {
+        position: usize,
+        entry_id: Option<String>,
+        found: String,
+    },
+    /// The backup ended while these elements were still open.
+    UnexpectedEof {
+        position: usize,
+        entry_id: Option<String>,
+        open: String,
+    },
+}
+
+impl ParseError {
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            ParseError::Io(_) | ParseError::Zip(_) => None,
+            ParseError::Xml { position, .. }
+            | ParseError::Utf8 { position, .. }
+            | ParseError::BadTimestamp { position, .. }
+            | ParseError::MissingField { position, .. }
+            | ParseError::UnexpectedKind { position, .. }
+            | ParseError::BadValue { position, .. }
+            | ParseError::MismatchedTag { position, .. }
+            | ParseError::UnexpectedEnd { position, .. }
+            | ParseError::UnexpectedEof { position, .. } => Some(*position),
+        }
+    }
+
+    pub fn entry_id(&self) -> Option<&str> {
+        match self {
+            ParseError::Io(_) | ParseError::Zip(_) => None,
+            ParseError::Xml { entry_id, .. }
+            | ParseError::Utf8 { entry_id, .. }
+            | ParseError::BadTimestamp { entry_id, .. }
+            | ParseError::MissingField { entry_id, .. }
+            | ParseError::UnexpectedKind { entry_id, .. }
+            | ParseError::BadValue { entry_id, .. }
+            | ParseError::MismatchedTag { entry_id, .. }
+            | ParseError::UnexpectedEnd { entry_id, .. }
+            | ParseError::UnexpectedEof { entry_id, .. } => entry_id.as_deref(),
+        }
+    }
+
+    /// What went wrong, without the entry id and position.
+    pub fn reason(&self) -> String {
+        match self {
+            ParseError::Io(e) => format!("io error: {}", e),
+            ParseError::Zip(e) => format!("zip error: {}", e),
+            ParseError::Xml { source, .. } => format!("xml error: {}", source),
+            ParseError::Utf8 { source, .. } => format!("invalid utf8: {}", source),
+            ParseError::BadTimestamp { value, source, .. } => {
+                format!("bad timestamp `{}`: {}", value, source)
+            }
+            ParseError::MissingField { field, .. } => format!("entry has no {}", field),
+            ParseError::UnexpectedKind { expected, .. } => {
+                format!("entry is not a {}", expected)
+            }
+            ParseError::BadValue { field, value, .. } => format!("bad {} `{}`", field, value),
+            ParseError::MismatchedTag {
+                expected, found, ..
+            } => format!("expected `</{}>` but found `</{}>`", expected, found),
+            ParseError::UnexpectedEnd { found, .. } => {
+                format!("found `</{}>` with no open element", found)
+            }
+            ParseError::UnexpectedEof { open, .. } => {
+                format!("the backup ended inside `{}`", open)
+            }
+        }
+    }
+
+    /// Fill in the position and entry id if the code that raised the error didn't know them.
+    pub fn located(mut self, at: usize, id: Option<&str>) -> Self {
+        match &mut self {
+            ParseError::Io(_) | ParseError::Zip(_) => (),
+            ParseError::Xml {
+                position, entry_id, ..
+            }
+            | ParseError::Utf8 {
+                position, entry_id, ..
+            }
+            | ParseError::BadTimestamp {
+                position, entry_id, ..
+            }
+            | ParseError::MissingField {
+                position, entry_id, ..
+            }
+            | ParseError::UnexpectedKind {
+                position, entry_id, ..
+            }
+            | ParseError::BadValue {
+                position, entry_id, ..
+            }
+            | ParseError::MismatchedTag {
+                position, entry_id, ..
+            }
+            | ParseError::UnexpectedEnd {
+                position, entry_id, ..
+            }
+            | ParseError::UnexpectedEof {
+                position, entry_id, ..
+            } => {
+                if *position == 0 {
+                    *position = at;
+                }
+                if entry_id.is_none() {
+                    *entry_id = id.map(str::to_owned);
+                }
+            }
+        }
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = self.reason();
+        match (self.entry_id(), self.position()) {
+            (Some(entry_id), Some(position)) => write!(
+                f,
+                "{} (entry {} at position {})",
+                reason, entry_id, position
+            ),
+            (None, Some(position)) => write!(f, "{} (at position {})", reason, position),
+            (_, None) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Io(e) => Some(e),
+            ParseError::Zip(e) => Some(e),
+            ParseError::Xml { source, .. } => Some(source),
+            ParseError::Utf8 { source, .. } => Some(source),
+            ParseError::BadTimestamp { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(error: io::Error) -> Self {
+        ParseError::Io(error)
+    }
+}
+
+impl From<quick_xml::Error> for ParseError {
+    fn from(error: quick_xml::Error) -> Self {
+        match error {
+            quick_xml::Error::Io(e) => ParseError::Io(e),
+            source => ParseError::Xml {
+                position: 0,
+                entry_id: None,
+                source,
+            },
+        }
+    }
+}
+
+impl From<zip::result::ZipError> for ParseError {
+    fn from(error: zip::result::ZipError) -> Self {
+        match error {
+            zip::result::ZipError::Io(e) => ParseError::Io(e),
+            error => ParseError::Zip(error),
+        }
+    }
+}
+
+impl From<FromUtf8Error> for ParseError {
+    fn from(source: FromUtf8Error) -> Self {
+        ParseError::Utf8 {
+            position: 0,
+            entry_id: None,
+            source,
+        }
+    }
+}
+
+/// A problem found in a backup that was parsed leniently.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub entry_id: Option<String>,
+    pub position: usize,
+    pub xpath: String,
+    pub reason: String,
+}
+
+impl Diagnostic {
+    pub fn from_error(error: &ParseError, xpath: String) -> Self {
+        Self {
+            entry_id: error.entry_id().map(str::to_owned),
+            position: error.position().unwrap_or_default(),
+            xpath,
+            reason: error.reason(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.entry_id {
+            Some(entry_id) => write!(
+                f,
+                "{} (entry {} at position {}, {})",
+                self.reason, entry_id, self.position, self.xpath
+            ),
+            None => write!(
+                f,
+                "{} (at position {}, {})",
+                self.reason, self.position, self.xpath
+            ),
+        }
+    }
+}
@@ -0,0 +1,175 @@
+/// Blogger ids, parsed out of their `tag:` uris.
+///
+/// Every entry is named by a tag like `tag:blogger.com,1999:blog-123.post-456`:
+/// the blog's number, then what the entry is and its own number.
+/// Comments are tagged `.post-` too, and comments on a page point at it as a post,
+/// so the kinds are kept apart by type rather than by the text of the tag.
+use std::fmt;
+use std::str::FromStr;
+
+use crate::errors::ParseError;
+
+const TAG_PREFIX: &str = "tag:blogger.com,1999:blog-";
+
+/// Split `tag:blogger.com,1999:blog-{blog}.{kind}-{number}` into its parts.
+/// The kind and number are missing from the blog's own tag.
+fn split_tag(tag: &str) -> Option<(u64, Option<(&str, &str)>)> {
+    let rest = tag.trim().strip_prefix(TAG_PREFIX)?;
+    let (blog, entry) = match rest.split_once('.') {
+        Some((blog, entry)) => (blog, entry.split_once('-')),
+        None => (rest, None),
+    };
+    Some((blog.parse().ok()?, entry))
+}
+
+/// The number of an entry tag of the given kind, with its blog.
+fn parse_entry_tag(tag: &str, kind: &str) -> Option<(BlogId, u64)> {
+    match split_tag(tag)? {
+        (blog, Some((k, number))) if k == kind => Some((BlogId(blog), number.parse().ok()?)),
+        _ => None,
+    }
+}
+
+fn bad_id(field: &'static str, value: &str) -> ParseError {
+    ParseError::BadValue {
+        position: 0,
+        entry_id: None,
+        field,
+        value: value.to_owned(),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlogId(u64);
+
+impl BlogId {
+    pub fn new(number: u64) -> Self {
+        Self(number)
+    }
+    /// The blog a tag belongs to, whether it names the blog itself
+    /// or something in it, such as `...blog-123.archive` or a post.
+    pub fn parse(tag: &str) -> Option<Self> {
+        split_tag(tag).map(|(blog, _)| Self(blog))
+    }
+    pub fn number(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for BlogId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", TAG_PREFIX, self.0)
+    }
+}
+
+impl FromStr for BlogId {
+    type Err = ParseError;
+    fn from_str(tag: &str) -> Result<Self, Self::Err> {
+        Self::parse(tag).ok_or_else(|| bad_id("blog id", tag))
+    }
+}
+
+/// Generate an id for one kind of entry in a blog: `...blog-{blog}.{kind}-{number}`.
+macro_rules! entry_id {
+    ($(#[$doc:meta])* $name:ident, $kind:literal, $field:literal) => {
+        $(#[$doc])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name {
+            blog: BlogId,
+            number: u64,
+        }
+
+        impl $name {
+            pub fn new(blog: BlogId, number: u64) -> Self {
+                Self { blog, number }
+            }
+            pub fn parse(tag: &str) -> Option<Self> {
+                let (blog, number) = parse_entry_tag(tag, $kind)?;
+                Some(Self { blog, number })
+            }
+            pub fn blog(&self) -> BlogId {
+                self.blog
+            }
+            /// The entry's own number, which is unique within the blog.
+            pub fn number(&self) -> u64 {
+                self.number
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}.{}-{}", self.blog, $kind, self.number)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ParseError;
+            fn from_str(tag: &str) -> Result<Self, Self::Err> {
+                Self::parse(tag).ok_or_else(|| bad_id($field, tag))
+            }
+        }
+    };
+}
+
+entry_id!(
+    /// A post, or the page a comment is on: comments point at pages with post ids.
+    PostId,
+    "post",
+    "post id"
+);
+entry_id!(
+    /// A comment.  Its tag looks like a post's, but it names a comment.
+    CommentId,
+    "post",
+    "comment id"
+);
+entry_id!(
+    /// A static page.
+    PageId,
+    "page",
+    "page id"
+);
+
+impl PageId {
+    /// The id comments on this page use for it:
+    /// `...blog-1.page-2` is commented on as `...blog-1.post-2`.
+    pub fn as_post_id(&self) -> PostId {
+        PostId::new(self.blog, self.number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids() {
+        let tag = "tag:blogger.com,1999:blog-4158473287126612345.post-8170941236547770012";
+        let post: PostId = tag.parse().unwrap();
+        assert_eq!(post.blog().number(), 4158473287126612345);
+        assert_eq!(post.number(), 8170941236547770012);
+        assert_eq!(post.to_string(), tag);
+        assert_eq!(post, PostId::parse(&format!(" {}\n", tag)).unwrap());
+        assert_eq!(CommentId::parse(tag).unwrap().number(), post.number());
+
+        let page = PageId::parse("tag:blogger.com,1999:blog-1.page-2").unwrap();
+        assert_eq!(
+            page.as_post_id().to_string(),
+            "tag:blogger.com,1999:blog-1.post-2"
+        );
+        assert_eq!(PostId::parse("tag:blogger.com,1999:blog-1.page-2"), None);
+
+        let blog = BlogId::parse("tag:blogger.com,1999:blog-42.archive").unwrap();
+        assert_eq!(blog.to_string(), "tag:blogger.com,1999:blog-42");
+        assert_eq!(blog, BlogId::new(42));
+
+        assert!(matches!(
+            "tag:blogger.com,1999:blog-1.post-x".parse::<PostId>(),
+            Err(ParseError::BadValue {
+                field: "post id",
+                ..
+            })
+        ));
+        assert!("post-2".parse::<CommentId>().is_err());
+    }
+}
@@ -0,0 +1,48 @@
+/// Helpers for working with post labels (Blogger's tags).
+use std::collections::BTreeMap;
+
+use crate::models::Post;
+
+/// Posts that carry `label`.
+pub fn with_label<'a>(posts: &'a [Post], label: &'a str) -> impl Iterator<Item = &'a Post> {
+    posts.iter().filter(move |post| post.has_label(label))
+}
+
+/// Posts that don't carry `label`.
+pub fn without_label<'a>(posts: &'a [Post], label: &'a str) -> impl Iterator<Item = &'a Post> {
+    posts.iter().filter(move |post| !post.has_label(label))
+}
+
+/// Every label in use, with the number of posts carrying it.
+pub fn label_counts(posts: &[Post]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for label in posts.iter().flat_map(|post| &post.labels) {
+        *counts.entry(label.to_owned()).or_insert(0) += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_backup::get_posts;
+
+    #[test]
+    fn test_labels() {
+        let posts = get_posts("data/backup.xml").unwrap();
+        let sled = &posts[1];
+        assert_eq!(sled.labels, vec!["Jigs", "Table Saw"]);
+        assert!(sled.has_label("Jigs"));
+        assert!(!sled.has_label("jigs"));
+
+        let titles: Vec<&str> = with_label(&posts, "Shop")
+            .map(|post| post.title.as_str())
+            .collect();
+        assert_eq!(titles, vec!["Hello from the garage"]);
+        assert_eq!(without_label(&posts, "Shop").count(), 2);
+
+        let counts = label_counts(&posts);
+        assert_eq!(counts.len(), 4);
+        assert_eq!(counts["Finishing"], 1);
+    }
+}
@@ -1,9 +1,50 @@
+pub mod archive;
+pub mod entry_reader;
 pub mod errors;
+pub mod ids;
+pub mod labels;
 pub mod models;
+pub mod namespaces;
+pub mod options;
 pub mod parse_backup;
+pub mod query;
+pub mod settings;
+pub mod status;
+pub mod template;
 pub mod utilities;
 pub mod xml_tools;
 
+pub use archive::parse_archive;
+pub use archive::parse_archive_with_options;
+pub use archive::parse_gzip;
+pub use archive::parse_zip;
+pub use archive::ArchivedBlog;
+pub use entry_reader::EntryReader;
+pub use errors::Diagnostic;
+pub use errors::ParseError;
+pub use ids::BlogId;
+pub use ids::CommentId;
+pub use ids::PageId;
+pub use ids::PostId;
+pub use models::Blog;
 pub use models::Comment;
+pub use models::ContentType;
+pub use models::ExportFormat;
+pub use models::Extension;
+pub use models::OrphanComment;
+pub use models::Page;
+pub use models::ParsedEntry;
 pub use models::Post;
+pub use models::PostStatus;
+pub use options::ParseOptions;
 pub use parse_backup::get_posts;
+pub use parse_backup::get_posts_from_bytes;
+pub use parse_backup::get_posts_from_reader;
+pub use parse_backup::get_posts_from_str;
+pub use parse_backup::get_posts_with_options;
+pub use parse_backup::parse_blog;
+pub use parse_backup::parse_blog_from_reader;
+pub use parse_backup::parse_blog_with_options;
+pub use query::PostQuery;
+pub use query::SortKey;
+pub use query::SortOrder;
@@ -1,23 +1,109 @@
-use parse_blogger_backup_xml::get_posts;
+use parse_blogger_backup_xml::parse_archive_with_options;
+use parse_blogger_backup_xml::status::status_counts;
+use parse_blogger_backup_xml::Blog;
+use parse_blogger_backup_xml::Diagnostic;
+use parse_blogger_backup_xml::ParseOptions;
+use parse_blogger_backup_xml::PostStatus;
 
 /// Parse the backup.xml file from a Google Blogger backup.
 ///
+/// Usage: `parse-blogger-backup-xml [--lenient] [--status=live,draft,...] <backup.xml | backup.xml.gz | takeout.zip>`
+///
+/// With `--lenient`, malformed entries are skipped or filled in
+/// and the problems are listed on stderr instead of stopping the parse.
+/// With `--status`, only posts and pages with one of the listed statuses
+/// (live, draft, scheduled, soft-trashed) are shown.
+/// Zip and gzip files are read without unpacking them,
+/// and every blog found in a Takeout zip is summarised in turn.
 fn main() {
-    // Read backup file location from command line
-    let args: Vec<String> = std::env::args().collect();
-    let backup_file_path = &args[1];
-    let posts = get_posts(backup_file_path).unwrap();
+    // Read backup file location and flags from command line
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let lenient = args.iter().any(|arg| arg == "--lenient");
+    let backup_file_path = match args.iter().find(|arg| !arg.starts_with("--")) {
+        Some(path) => path,
+        None => {
+            eprintln!("{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    let statuses = match args.iter().find_map(|arg| arg.strip_prefix("--status=")) {
+        Some(names) => match parse_statuses(names) {
+            Some(statuses) => statuses,
+            None => {
+                eprintln!("Unknown status in `{names}`\n{USAGE}");
+                std::process::exit(2);
+            }
+        },
+        None => PostStatus::ALL.to_vec(),
+    };
+    let options = if lenient {
+        ParseOptions::lenient()
+    } else {
+        ParseOptions::strict()
+    }
+    .statuses(&statuses);
+    let blogs = match parse_archive_with_options(backup_file_path, &options) {
+        Ok(blogs) => blogs,
+        Err(error) => {
+            eprintln!("Could not parse {backup_file_path}: {error}");
+            std::process::exit(1);
+        }
+    };
+    if blogs.is_empty() {
+        eprintln!("No blogger feeds found in {backup_file_path}");
+        std::process::exit(1);
+    }
+
+    let mut failed = false;
+    for archived in blogs {
+        match archived.result {
+            Ok((blog, diagnostics)) => print_blog(&blog, &diagnostics),
+            Err(error) => {
+                eprintln!("Could not parse {}: {error}", archived.path);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
 
-    // Print out posts
-    println!("{:#?}", &posts);
+const USAGE: &str = "Usage: parse-blogger-backup-xml [--lenient] [--status=live,draft,...] \
+    <backup.xml | backup.xml.gz | takeout.zip>";
+
+/// A comma separated list of status names, e.g. `live,scheduled`.
+fn parse_statuses(names: &str) -> Option<Vec<PostStatus>> {
+    names.split(',').map(PostStatus::from_name).collect()
+}
+
+fn print_blog(blog: &Blog, diagnostics: &[Diagnostic]) {
+    // Print out posts and pages
+    let posts = &blog.posts;
+    println!("{:#?}", posts);
+    println!("{:#?}", &blog.pages);
 
     // Print out the posts length
     let post_count = posts.len();
-    println!("\n{post_count} posts in total");
+    println!("\n{} ({})", blog.title, blog.id);
+    println!("{post_count} posts in total");
+    for (status, count) in status_counts(posts) {
+        println!("  {count} {status}");
+    }
+    println!("{} pages in total", blog.pages.len());
 
     if !posts.is_empty() {
         let first_post_published = &posts[0].published;
         let last_post_published = &posts[post_count - 1].published;
         println!("published from {first_post_published} to {last_post_published}");
     }
+
+    let orphan_count = blog.orphan_comments.len();
+    if orphan_count > 0 {
+        println!("{orphan_count} comments whose post is missing");
+    }
+
+    for diagnostic in diagnostics {
+        eprintln!("warning: {diagnostic}");
+    }
 }
@@ -1,136 +1,699 @@
 use crate::errors::EmptyResult;
+use crate::errors::ParseError;
+use crate::errors::ParseResult;
+use crate::ids::BlogId;
+use crate::ids::CommentId;
+use crate::ids::PageId;
+use crate::ids::PostId;
+use crate::settings::BlogSettings;
 use crate::utilities;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
 use chrono::DateTime;
 use chrono::Datelike;
 use chrono::FixedOffset;
 use chrono::Timelike;
+/// An atom `<link>`: `rel` is one of alternate, replies, self, edit, related...
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Link {
+    pub href: String,
+    pub media_type: Option<String>,
+    pub rel: String,
+    pub title: Option<String>,
+}
+
+const ANONYMOUS_NAME: &str = "Anonymous";
+const ANONYMOUS_TYPE: &str = "ANONYMOUS";
+const PLACEHOLDER_EMAIL: &str = "noreply@blogger.com";
+const PLACEHOLDER_IMAGES: [&str; 2] = [
+    "https://img1.blogblog.com/img/blank.gif",
+    "https://img1.blogblog.com/img/b16-rounded.gif",
+];
+
+/// The author of the feed, a post or a comment.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Author {
+    /// Takeout's `blogger:type` for the author, such as `BLOGGER` or `ANONYMOUS`.
+    pub author_type: Option<String>,
+    pub email: Option<String>,
+    /// The `gd:image` avatar, with protocol-relative urls made https.
+    pub image_url: Option<String>,
+    pub name: String,
+    /// The blogger profile, which tells apart authors who share a name.
+    pub uri: Option<String>,
+}
+
+impl Author {
+    /// Whether the author didn't sign in.  Takeout says so with `blogger:type`,
+    /// classic backups by naming them "Anonymous" without a profile.
+    pub fn is_anonymous(&self) -> bool {
+        match self.author_type.as_deref() {
+            Some(author_type) => author_type == ANONYMOUS_TYPE,
+            None => self.name == ANONYMOUS_NAME && self.uri.is_none(),
+        }
+    }
+    /// Blogger hides real addresses behind `noreply@blogger.com`.
+    pub fn has_placeholder_email(&self) -> bool {
+        self.email.as_deref() == Some(PLACEHOLDER_EMAIL)
+    }
+    /// The email address, unless it's the blogger placeholder.
+    pub fn real_email(&self) -> Option<&str> {
+        self.email
+            .as_deref()
+            .filter(|email| *email != PLACEHOLDER_EMAIL)
+    }
+    /// The avatar, unless it's one of blogger's default images.
+    pub fn real_image_url(&self) -> Option<&str> {
+        self.image_url
+            .as_deref()
+            .filter(|url| !PLACEHOLDER_IMAGES.contains(url))
+    }
+}
+
+fn find_link<'a>(links: &'a [Link], rel: &str) -> Option<&'a Link> {
+    links.iter().find(|link| link.rel == rel)
+}
+
+/// The path of a url, without the query or fragment.
+fn url_path(url: &str) -> Option<&str> {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let path = &without_scheme[without_scheme.find('/')?..];
+    path.split(['?', '#']).next()
+}
+
+/// The atom `type` of a title or content, which says how to treat the string.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ContentType {
+    /// Plain text, which has to be escaped before it goes into html.
+    #[default]
+    Text,
+    /// Html that was escaped in the feed and is ready to use.
+    Html,
+    /// Inline xhtml, kept as markup without its wrapping `<div>`.
+    Xhtml,
+    /// A media type such as `text/markdown`.
+    Other(String),
+}
+
+impl ContentType {
+    /// Atom treats a missing `type` as text.
+    pub fn from_attribute(value: Option<&str>) -> Self {
+        match value {
+            None | Some("text") => ContentType::Text,
+            Some("html") => ContentType::Html,
+            Some("xhtml") => ContentType::Xhtml,
+            Some(other) => ContentType::Other(other.to_owned()),
+        }
+    }
+    /// Whether the string must be escaped to show it in html.
+    pub fn needs_escaping(&self) -> bool {
+        !matches!(self, ContentType::Html | ContentType::Xhtml)
+    }
+}
+
+/// Where a post or page is in its life.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PostStatus {
+    #[default]
+    Live,
+    Draft,
+    /// Published after the backup was made, so not yet visible.
+    Scheduled,
+    /// Deleted, but still in a Takeout export.
+    SoftTrashed,
+}
+
+impl PostStatus {
+    pub const ALL: [PostStatus; 4] = [
+        PostStatus::Live,
+        PostStatus::Draft,
+        PostStatus::Scheduled,
+        PostStatus::SoftTrashed,
+    ];
+
+    /// Read a Takeout `blogger:status`.
+    pub fn from_takeout(value: &str) -> Option<Self> {
+        match value {
+            "LIVE" => Some(PostStatus::Live),
+            "DRAFT" => Some(PostStatus::Draft),
+            "SCHEDULED" => Some(PostStatus::Scheduled),
+            "SOFT_TRASHED" => Some(PostStatus::SoftTrashed),
+            _ => None,
+        }
+    }
+    /// Parse the lowercase name shown by `Display`, e.g. `soft-trashed`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.to_string() == name)
+    }
+}
+
+impl fmt::Display for PostStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PostStatus::Live => "live",
+            PostStatus::Draft => "draft",
+            PostStatus::Scheduled => "scheduled",
+            PostStatus::SoftTrashed => "soft-trashed",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Comment {
-    pub author_name: String,
+    pub author: Author,
     pub content: String,
-    pub id: String,
-    pub post_id: String,
+    pub content_type: ContentType,
+    pub extensions: Extensions,
+    pub id: CommentId,
+    /// The comment this one replies to, for threaded discussions.
+    pub in_reply_to: Option<CommentId>,
+    pub links: Vec<Link>,
+    /// The post or page the comment is on.  Pages are referred to by post id, see `PageId::as_post_id`.
+    pub post_id: PostId,
     pub published: DateTime<FixedOffset>,
     pub title: String,
+    pub title_type: ContentType,
+    pub updated: DateTime<FixedOffset>,
 }
 
 #[derive(Clone, Debug)]
 pub struct Post {
-    pub author_name: String,
+    pub author: Author,
     pub comments: Vec<Comment>,
     pub content: String,
-    pub draft: bool,
-    pub id: String,
+    pub content_type: ContentType,
+    /// The comment count from `thr:total`, which may not match `comments.len()`.
+    pub declared_comment_count: Option<u32>,
+    pub extensions: Extensions,
+    /// The permalink path from a Takeout export's `blogger:filename`.
+    pub filename: Option<String>,
+    pub id: PostId,
+    pub labels: Vec<String>,
+    pub links: Vec<Link>,
     pub published: DateTime<FixedOffset>,
+    pub status: PostStatus,
     pub title: String,
+    pub title_type: ContentType,
+    pub updated: DateTime<FixedOffset>,
+}
+
+impl Comment {
+    pub fn link(&self, rel: &str) -> Option<&Link> {
+        find_link(&self.links, rel)
+    }
+    /// Where the comment was shown on the blog.
+    pub fn url(&self) -> Option<&str> {
+        self.link("alternate").map(|link| link.href.as_str())
+    }
+}
+
+/// A comment whose post or page isn't in the backup, usually because it was deleted.
+#[derive(Clone, Debug)]
+pub struct OrphanComment {
+    pub comment: Comment,
+    /// Why the comment couldn't be attached, e.g. which post it was looking for.
+    pub reason: String,
+}
+
+/// A comment together with the replies to it, as returned by `Post::comment_tree`.
+#[derive(Clone, Debug)]
+pub struct CommentNode<'a> {
+    pub comment: &'a Comment,
+    pub replies: Vec<CommentNode<'a>>,
 }
 
 impl Post {
+    /// The comments nested by `in_reply_to`, oldest first at every level.
+    /// Replies whose parent isn't among the comments are shown at the top level.
+    pub fn comment_tree(&self) -> Vec<CommentNode<'_>> {
+        let mut comments: Vec<&Comment> = self.comments.iter().collect();
+        comments.sort_by_key(|comment| comment.published);
+        let ids: HashSet<CommentId> = comments.iter().map(|c| c.id).collect();
+        let mut replies: HashMap<CommentId, Vec<&Comment>> = HashMap::new();
+        let mut roots = Vec::new();
+        for comment in &comments {
+            match comment.in_reply_to {
+                Some(parent) if ids.contains(&parent) && parent != comment.id => {
+                    replies.entry(parent).or_default().push(comment)
+                }
+                _ => roots.push(*comment),
+            }
+        }
+        let mut placed = HashSet::new();
+        let mut tree: Vec<CommentNode> = roots
+            .into_iter()
+            .map(|root| build_node(root, &replies, &mut placed))
+            .collect();
+        // Comments caught in a reply cycle never hang off a root, so give them their own.
+        for comment in comments {
+            if !placed.contains(&comment.id) {
+                tree.push(build_node(comment, &replies, &mut placed));
+            }
+        }
+        tree.sort_by_key(|node| node.comment.published);
+        tree
+    }
+
+    pub fn link(&self, rel: &str) -> Option<&Link> {
+        find_link(&self.links, rel)
+    }
+    /// The public url of the post.  Drafts don't have one.
+    pub fn url(&self) -> Option<&str> {
+        self.link("alternate").map(|link| link.href.as_str())
+    }
+    /// The declared and attached comment counts, when they disagree.
+    /// A mismatch usually means comments were lost from the backup.
+    pub fn comment_count_mismatch(&self) -> Option<(u32, usize)> {
+        comment_count_mismatch(self.declared_comment_count, &self.comments)
+    }
+    /// The path part of the public url, e.g. `/2014/05/a-post.html`.
+    pub fn permalink_path(&self) -> Option<&str> {
+        self.url().and_then(url_path).or(self.filename.as_deref())
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.status == PostStatus::Live
+    }
+
+    pub fn has_label(&self, label: &str) -> bool {
+        self.labels.iter().any(|l| l == label)
+    }
+
     pub fn save_content(&self) -> EmptyResult {
-        let path = format!(
-            "data/bookroot/post_content_for_{}-{}-{}-{}-{}-{}",
-            self.published.year(),
-            self.published.month(),
-            self.published.day(),
-            self.published.hour(),
-            self.published.minute(),
-            self.published.second(),
-        );
-        let content = self.content.clone();
-        utilities::save(&path, content)?;
-        Ok(())
+        save_content("post", &self.published, &self.content)
     }
 }
 
+/// A static page such as "About" or "Contact".
 #[derive(Clone, Debug)]
+pub struct Page {
+    pub author: Author,
+    pub comments: Vec<Comment>,
+    pub content: String,
+    pub content_type: ContentType,
+    pub declared_comment_count: Option<u32>,
+    pub extensions: Extensions,
+    pub filename: Option<String>,
+    pub id: PageId,
+    pub links: Vec<Link>,
+    pub published: DateTime<FixedOffset>,
+    pub status: PostStatus,
+    pub title: String,
+    pub title_type: ContentType,
+    pub updated: DateTime<FixedOffset>,
+}
+
+impl Page {
+    pub fn link(&self, rel: &str) -> Option<&Link> {
+        find_link(&self.links, rel)
+    }
+    pub fn url(&self) -> Option<&str> {
+        self.link("alternate").map(|link| link.href.as_str())
+    }
+    /// The path part of the public url, e.g. `/p/about.html`.
+    pub fn permalink_path(&self) -> Option<&str> {
+        self.url().and_then(url_path).or(self.filename.as_deref())
+    }
+    /// The declared and attached comment counts, when they disagree.
+    pub fn comment_count_mismatch(&self) -> Option<(u32, usize)> {
+        comment_count_mismatch(self.declared_comment_count, &self.comments)
+    }
+    /// Comments on a page point at it with a post id:
+    /// `...blog-1.page-2` is commented on as `...blog-1.post-2`.
+    pub fn comment_post_id(&self) -> PostId {
+        self.id.as_post_id()
+    }
+    pub fn save_content(&self) -> EmptyResult {
+        save_content("page", &self.published, &self.content)
+    }
+}
+
+/// Save the content of a post or page under `data/bookroot`, named by kind and publish time.
+fn save_content(kind: &str, published: &DateTime<FixedOffset>, content: &str) -> EmptyResult {
+    let path = format!(
+        "data/bookroot/{}_content_for_{}-{}-{}-{}-{}-{}",
+        kind,
+        published.year(),
+        published.month(),
+        published.day(),
+        published.hour(),
+        published.minute(),
+        published.second(),
+    );
+    utilities::save(&path, content.to_owned())?;
+    Ok(())
+}
+
+fn comment_count_mismatch(declared: Option<u32>, comments: &[Comment]) -> Option<(u32, usize)> {
+    match declared {
+        Some(declared) if declared as usize != comments.len() => Some((declared, comments.len())),
+        _ => None,
+    }
+}
+
+fn build_node<'a>(
+    comment: &'a Comment,
+    replies: &HashMap<CommentId, Vec<&'a Comment>>,
+    placed: &mut HashSet<CommentId>,
+) -> CommentNode<'a> {
+    placed.insert(comment.id);
+    let mut children = Vec::new();
+    for child in replies.get(&comment.id).into_iter().flatten() {
+        if !placed.contains(&child.id) {
+            children.push(build_node(child, replies, placed));
+        }
+    }
+    CommentNode {
+        comment,
+        replies: children,
+    }
+}
+
+/// The software that wrote the backup, normally `Blogger`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Generator {
+    pub name: String,
+    pub uri: Option<String>,
+    pub version: Option<String>,
+}
+
+/// Which dialect of Atom a backup is written in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// The `Settings > Back up content` download, with `kind#...` categories.
+    #[default]
+    Classic,
+    /// The Google Takeout `feed.atom`, with `blogger:type` and friends.
+    Takeout,
+}
+
+/// What the feed says about itself before the first entry.
+#[derive(Clone, Debug, Default)]
+pub struct FeedHeader {
+    pub author: Option<Author>,
+    pub format: ExportFormat,
+    pub generator: Option<Generator>,
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub updated: Option<DateTime<FixedOffset>>,
+}
+
+/// A whole blog: the feed header together with everything in it.
+#[derive(Clone, Debug)]
+pub struct Blog {
+    pub author: Option<Author>,
+    pub format: ExportFormat,
+    pub generator: Option<Generator>,
+    pub id: String,
+    /// Comments whose post couldn't be found in the backup.
+    pub orphan_comments: Vec<OrphanComment>,
+    /// Entries of kinds this crate doesn't model yet.
+    pub other_entries: Vec<Entry>,
+    pub pages: Vec<Page>,
+    pub posts: Vec<Post>,
+    pub settings: BlogSettings,
+    /// The layout template, see `Template::layout`.
+    pub template: Option<Template>,
+    pub title: String,
+    pub updated: Option<DateTime<FixedOffset>>,
+}
+
+impl Blog {
+    /// The blog's number, from the feed id `tag:blogger.com,1999:blog-{blog}.archive`.
+    pub fn blog_id(&self) -> Option<BlogId> {
+        BlogId::parse(&self.id)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Setting {
+    pub id: String,
+    pub name: String,
+    pub value: String,
+}
+
+/// The blog's layout template, as xhtml.
+#[derive(Clone, Debug)]
+pub struct Template {
+    pub id: String,
+    pub content: String,
+}
+
+/// An entry from the backup, converted into the model that matches its kind.
+#[derive(Clone, Debug)]
+pub enum ParsedEntry {
+    Comment(Comment),
+    Page(Page),
+    Post(Post),
+    Settings(Setting),
+    Template(Template),
+    /// An entry of a kind this crate doesn't model, left as it was read.
+    Other(Entry),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum EntryKind {
     Comment,
+    Page,
     Post,
     Settings,
     Template,
+    /// A `kind#...` category term this crate doesn't know about yet.
+    Other(String),
 }
+
+/// An element inside an entry that the parser doesn't understand,
+/// such as `georss:point` or `media:thumbnail`.
+/// `text` holds all the text inside it, including that of nested elements.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Extension {
+    pub attributes: Vec<(String, String)>,
+    /// The name with blogger's usual prefix, see `QName`.
+    pub name: String,
+    pub namespace: Option<String>,
+    pub text: String,
+}
+
+impl Extension {
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Unrecognised elements by name, in document order.
+pub type Extensions = BTreeMap<String, Vec<Extension>>;
 #[derive(Clone, Debug, Default)]
 pub struct Entry {
-    pub author_name: Option<String>,
+    pub author: Option<Author>,
     pub content: Option<String>,
-    pub draft: bool,
+    pub content_type: ContentType,
+    pub declared_comment_count: Option<u32>,
+    pub extensions: Extensions,
+    pub filename: Option<String>,
     pub id: Option<String>,
+    /// The parent comment, when the export names it directly (`blogger:inReplyTo`).
+    pub in_reply_to: Option<String>,
     pub kind: Option<EntryKind>,
+    pub labels: Vec<String>,
+    pub links: Vec<Link>,
     pub post_id: Option<String>,
     pub published: Option<DateTime<FixedOffset>>,
+    /// From `app:draft` or a Takeout export's `blogger:status`.
+    pub status: PostStatus,
+    /// A Takeout export's `blogger:status` as written.
+    /// It only becomes a `PostStatus` for posts and pages,
+    /// and the kind may not be known yet when it is read.
+    pub takeout_status: Option<String>,
     pub title: Option<String>,
+    pub title_type: ContentType,
+    pub updated: Option<DateTime<FixedOffset>>,
 }
 
 impl Entry {
     pub fn new() -> Entry {
         Entry {
-            author_name: None,
+            author: None,
             content: None,
-            draft: false,
+            content_type: ContentType::default(),
+            declared_comment_count: None,
+            extensions: Extensions::new(),
+            filename: None,
             id: None,
+            in_reply_to: None,
             kind: None,
+            labels: vec![],
+            links: vec![],
             post_id: None,
             published: None,
+            status: PostStatus::default(),
+            takeout_status: None,
             title: None,
+            title_type: ContentType::default(),
+            updated: None,
         }
     }
-    pub fn to_post(&self) -> Option<Post> {
-        if let Entry {
-            author_name: Some(author_name),
-            content: Some(content),
-            draft,
-            kind: Some(EntryKind::Post),
-            id: Some(id),
-            published: Some(published),
-            title: Some(title),
-            ..
-        } = self
-        {
-            Some(Post {
-                author_name: author_name.to_owned(),
-                comments: vec![],
-                content: content.to_owned(),
-                draft: draft.to_owned(),
-                id: id.to_owned(),
-                published: published.to_owned(),
-                title: title.to_owned(),
-            })
+    pub fn to_post(&self) -> ParseResult<Post> {
+        self.expect_kind(EntryKind::Post, "post")?;
+        Ok(Post {
+            author: self.required(&self.author, "author")?.to_owned(),
+            comments: vec![],
+            content: self.required(&self.content, "content")?.to_owned(),
+            content_type: self.content_type.to_owned(),
+            declared_comment_count: self.declared_comment_count,
+            extensions: self.extensions.to_owned(),
+            filename: self.filename.to_owned(),
+            id: self.required_id(&self.id, "id")?,
+            labels: self.labels.to_owned(),
+            links: self.links.to_owned(),
+            published: self.required(&self.published, "published")?.to_owned(),
+            status: self.status,
+            title: self.required(&self.title, "title")?.to_owned(),
+            title_type: self.title_type.to_owned(),
+            updated: self.required(&self.updated, "updated")?.to_owned(),
+        })
+    }
+    pub fn to_page(&self) -> ParseResult<Page> {
+        self.expect_kind(EntryKind::Page, "page")?;
+        Ok(Page {
+            author: self.required(&self.author, "author")?.to_owned(),
+            comments: vec![],
+            content: self.required(&self.content, "content")?.to_owned(),
+            content_type: self.content_type.to_owned(),
+            declared_comment_count: self.declared_comment_count,
+            extensions: self.extensions.to_owned(),
+            filename: self.filename.to_owned(),
+            id: self.required_id(&self.id, "id")?,
+            links: self.links.to_owned(),
+            published: self.required(&self.published, "published")?.to_owned(),
+            status: self.status,
+            title: self.required(&self.title, "title")?.to_owned(),
+            title_type: self.title_type.to_owned(),
+            updated: self.required(&self.updated, "updated")?.to_owned(),
+        })
+    }
+    pub fn to_comment(&self) -> ParseResult<Comment> {
+        self.expect_kind(EntryKind::Comment, "comment")?;
+        Ok(Comment {
+            author: self.required(&self.author, "author")?.to_owned(),
+            content: self.required(&self.content, "content")?.to_owned(),
+            content_type: self.content_type.to_owned(),
+            extensions: self.extensions.to_owned(),
+            id: self.required_id(&self.id, "id")?,
+            in_reply_to: match &self.in_reply_to {
+                Some(parent) => Some(parent.parse()?),
+                None => self.parent_comment_id(),
+            },
+            links: self.links.to_owned(),
+            post_id: self.parent_post_id()?,
+            published: self.required(&self.published, "published")?.to_owned(),
+            title: self.required(&self.title, "title")?.to_owned(),
+            title_type: self.title_type.to_owned(),
+            updated: self.required(&self.updated, "updated")?.to_owned(),
+        })
+    }
+    pub fn to_setting(&self) -> ParseResult<Setting> {
+        self.expect_kind(EntryKind::Settings, "setting")?;
+        let id = self.required(&self.id, "id")?;
+        // The key ends the id (`...blog-123.settings.BLOG_NAME`) and is repeated as the title.
+        let name = match id.split_once(".settings.") {
+            Some((_, key)) => key,
+            None => self.required(&self.title, "title")?,
+        };
+        Ok(Setting {
+            id: id.to_owned(),
+            name: name.to_owned(),
+            value: self.content.to_owned().unwrap_or_default(),
+        })
+    }
+    pub fn to_template(&self) -> ParseResult<Template> {
+        self.expect_kind(EntryKind::Template, "template")?;
+        Ok(Template {
+            id: self.required(&self.id, "id")?.to_owned(),
+            content: self.required(&self.content, "content")?.to_owned(),
+        })
+    }
+    pub fn add_extension(&mut self, extension: Extension) {
+        self.extensions
+            .entry(extension.name.to_owned())
+            .or_default()
+            .push(extension);
+    }
+    /// The first unrecognised element with this name.
+    pub fn extension(&self, name: &str) -> Option<&Extension> {
+        self.extensions.get(name)?.first()
+    }
+    /// Replies link to their parent comment's feed with `rel="related"`:
+    /// `https://www.blogger.com/feeds/{blog}/{post}/comments/default/{comment}`.
+    fn parent_comment_id(&self) -> Option<CommentId> {
+        let related = find_link(&self.links, "related")?;
+        let path = related.href.split_once("/feeds/")?.1;
+        let blog = path.split('/').next()?.parse().ok()?;
+        let comment = path.rsplit('/').next()?.parse().ok()?;
+        Some(CommentId::new(BlogId::new(blog), comment))
+    }
+    fn expect_kind(&self, kind: EntryKind, expected: &'static str) -> ParseResult<()> {
+        if self.kind == Some(kind) {
+            Ok(())
         } else {
-            None
+            Err(ParseError::UnexpectedKind {
+                position: 0,
+                entry_id: self.id.to_owned(),
+                expected,
+            })
         }
     }
-    pub fn to_comment(&self) -> Option<Comment> {
-        if let Entry {
-            author_name: Some(author_name),
-            content: Some(content),
-            draft: _draft,
-            kind: Some(EntryKind::Comment),
-            id: Some(id),
-            published: Some(published),
-            title: Some(title),
-            post_id: Some(post_id),
-        } = self
-        {
-            Some(Comment {
-                author_name: author_name.to_owned(),
-                content: content.to_owned(),
-                id: id.to_owned(),
-                post_id: post_id.to_owned(),
-                published: published.to_owned(),
-                title: title.to_owned(),
-            })
-        } else {
-            None
+    /// The post or page a comment is on.  Classic backups refer to pages by post id,
+    /// Takeout by the page's own id, which is turned into the post id comments use.
+    fn parent_post_id(&self) -> ParseResult<PostId> {
+        let tag = self.required(&self.post_id, "post id")?;
+        match PageId::parse(tag) {
+            Some(page) => Ok(page.as_post_id()),
+            None => tag.parse(),
         }
     }
+    /// A required id, parsed from its tag.
+    fn required_id<T: FromStr<Err = ParseError>>(
+        &self,
+        value: &Option<String>,
+        field: &'static str,
+    ) -> ParseResult<T> {
+        self.required(value, field)?.parse()
+    }
+    fn required<'a, T>(&self, value: &'a Option<T>, field: &'static str) -> ParseResult<&'a T> {
+        value.as_ref().ok_or_else(|| ParseError::MissingField {
+            position: 0,
+            entry_id: self.id.to_owned(),
+            field,
+        })
+    }
     pub fn clear(&mut self) {
-        self.author_name = None;
+        self.author = None;
         self.content = None;
-        self.draft = false;
+        self.content_type = ContentType::default();
+        self.declared_comment_count = None;
+        self.extensions.clear();
+        self.filename = None;
         self.id = None;
+        self.in_reply_to = None;
         self.kind = None;
+        self.labels.clear();
+        self.links.clear();
         self.post_id = None;
         self.published = None;
+        self.status = PostStatus::default();
+        self.takeout_status = None;
         self.title = None;
+        self.title_type = ContentType::default();
+        self.updated = None;
     }
 }
@@ -0,0 +1,33 @@
+/// The xml namespaces found in blogger exports.
+///
+/// Elements are matched on their namespace uri, not on the prefix a file happens to use,
+/// so `<t:total xmlns:t="http://purl.org/syndication/thread/1.0">` is read as `thr:total`.
+pub const ATOM: &str = "http://www.w3.org/2005/Atom";
+/// The atom publishing protocol, as used by classic backups.
+pub const APP: &str = "http://purl.org/atom/app#";
+/// The published atom publishing protocol, which some tools write instead.
+pub const APP_2007: &str = "http://www.w3.org/2007/app";
+/// Google Takeout's `blogger:type`, `blogger:status` and friends.
+pub const BLOGGER: &str = "http://schemas.google.com/blogger/2018";
+pub const GD: &str = "http://schemas.google.com/g/2005";
+pub const GEORSS: &str = "http://www.georss.org/georss";
+pub const MEDIA: &str = "http://search.yahoo.com/mrss/";
+pub const OPENSEARCH: &str = "http://a9.com/-/spec/opensearchrss/1.0/";
+pub const THR: &str = "http://purl.org/syndication/thread/1.0";
+/// Inline `type="xhtml"` titles and content.
+pub const XHTML: &str = "http://www.w3.org/1999/xhtml";
+
+/// The prefix blogger itself uses for a namespace, or `""` for atom.
+pub fn prefix(uri: &str) -> Option<&'static str> {
+    match uri {
+        ATOM => Some(""),
+        APP | APP_2007 => Some("app"),
+        BLOGGER => Some("blogger"),
+        GD => Some("gd"),
+        GEORSS => Some("georss"),
+        MEDIA => Some("media"),
+        OPENSEARCH => Some("openSearch"),
+        THR => Some("thr"),
+        _ => None,
+    }
+}
@@ -0,0 +1,184 @@
+use chrono::DateTime;
+use chrono::FixedOffset;
+
+use crate::models::Entry;
+use crate::models::EntryKind;
+use crate::models::PostStatus;
+
+/// Settings that change how a backup is parsed and which entries are kept.
+///
+/// The filters are applied while the backup is streamed:
+/// once an entry is known to be unwanted its content is no longer buffered.
+///
+/// ```
+/// use chrono::{Duration, Utc};
+/// use parse_blogger_backup_xml::{ParseOptions, PostStatus};
+///
+/// let a_year_ago = (Utc::now() - Duration::days(365)).into();
+/// let options = ParseOptions::lenient()
+///     .statuses(&[PostStatus::Live])
+///     .published_after(a_year_ago)
+///     .include_pages(false);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ParseOptions {
+    /// In strict mode the first malformed entry stops the parse with an error.
+    /// Otherwise bad entries are skipped or partially filled in,
+    /// and each problem is reported as a `Diagnostic`.
+    pub strict: bool,
+    /// Posts and pages with other statuses are skipped.
+    pub statuses: Vec<PostStatus>,
+    pub include_comments: bool,
+    pub include_pages: bool,
+    pub include_settings: bool,
+    pub include_templates: bool,
+    /// Posts and pages published before this are skipped.
+    pub published_after: Option<DateTime<FixedOffset>>,
+    /// Posts and pages published after this are skipped.
+    pub published_before: Option<DateTime<FixedOffset>>,
+    /// When not empty, only posts with at least one of these labels are kept.
+    pub allowed_labels: Vec<String>,
+    /// Posts with any of these labels are skipped.
+    pub denied_labels: Vec<String>,
+    /// Entries whose content is longer than this many bytes are skipped with a diagnostic.
+    pub max_content_size: Option<usize>,
+}
+
+impl ParseOptions {
+    pub fn strict() -> Self {
+        Self {
+            strict: true,
+            statuses: PostStatus::ALL.to_vec(),
+            include_comments: true,
+            include_pages: true,
+            include_settings: true,
+            include_templates: true,
+            published_after: None,
+            published_before: None,
+            allowed_labels: Vec::new(),
+            denied_labels: Vec::new(),
+            max_content_size: None,
+        }
+    }
+    pub fn lenient() -> Self {
+        Self {
+            strict: false,
+            ..Self::strict()
+        }
+    }
+
+    /// Keep only posts and pages with one of these statuses.
+    pub fn statuses(mut self, statuses: &[PostStatus]) -> Self {
+        self.statuses = statuses.to_vec();
+        self
+    }
+    /// Keep or skip draft posts and pages.
+    pub fn include_drafts(mut self, include: bool) -> Self {
+        self.statuses.retain(|status| *status != PostStatus::Draft);
+        if include {
+            self.statuses.push(PostStatus::Draft);
+        }
+        self
+    }
+    pub fn include_comments(mut self, include: bool) -> Self {
+        self.include_comments = include;
+        self
+    }
+    pub fn include_pages(mut self, include: bool) -> Self {
+        self.include_pages = include;
+        self
+    }
+    pub fn include_settings(mut self, include: bool) -> Self {
+        self.include_settings = include;
+        self
+    }
+    pub fn include_templates(mut self, include: bool) -> Self {
+        self.include_templates = include;
+        self
+    }
+    pub fn published_after(mut self, after: DateTime<FixedOffset>) -> Self {
+        self.published_after = Some(after);
+        self
+    }
+    pub fn published_before(mut self, before: DateTime<FixedOffset>) -> Self {
+        self.published_before = Some(before);
+        self
+    }
+    /// Keep only posts with this label, or any other allowed one.
+    pub fn allow_label(mut self, label: &str) -> Self {
+        self.allowed_labels.push(label.to_owned());
+        self
+    }
+    /// Skip posts with this label.
+    pub fn deny_label(mut self, label: &str) -> Self {
+        self.denied_labels.push(label.to_owned());
+        self
+    }
+    pub fn max_content_size(mut self, bytes: usize) -> Self {
+        self.max_content_size = Some(bytes);
+        self
+    }
+
+    /// Whether every comment on a kept post or page is kept too,
+    /// so that the comments found can be checked against the declared count.
+    pub fn keeps_every_comment(&self) -> bool {
+        self.include_comments && self.max_content_size.is_none()
+    }
+
+    /// Whether what has been read of an entry so far already rules it out.
+    /// Nothing read later can bring it back, so the rest of it needn't be kept.
+    pub(crate) fn rejects(&self, entry: &Entry) -> bool {
+        match entry.kind {
+            Some(EntryKind::Comment) => !self.include_comments,
+            Some(EntryKind::Settings) => !self.include_settings,
+            Some(EntryKind::Template) => !self.include_templates,
+            Some(EntryKind::Page) => !self.include_pages || self.rejects_published(entry),
+            Some(EntryKind::Post) => {
+                self.rejects_published(entry)
+                    || entry
+                        .labels
+                        .iter()
+                        .any(|label| self.denied_labels.contains(label))
+            }
+            Some(EntryKind::Other(_)) | None => false,
+        }
+    }
+
+    /// Whether a complete entry should be kept.
+    /// The status and allowed labels are only checked here:
+    /// Takeout gives the status late, a post only turns out to be scheduled
+    /// once it has all been read, and an allowed label may still be to come.
+    pub(crate) fn accepts(&self, entry: &Entry) -> bool {
+        if self.rejects(entry) {
+            return false;
+        }
+        match entry.kind {
+            Some(EntryKind::Page) => self.statuses.contains(&entry.status),
+            Some(EntryKind::Post) => {
+                self.statuses.contains(&entry.status)
+                    && (self.allowed_labels.is_empty()
+                        || entry
+                            .labels
+                            .iter()
+                            .any(|label| self.allowed_labels.contains(label)))
+            }
+            _ => true,
+        }
+    }
+
+    /// Whether the entry was published outside the date window.
+    fn rejects_published(&self, entry: &Entry) -> bool {
+        let published = match entry.published {
+            Some(published) => published,
+            None => return false,
+        };
+        matches!(self.published_after, Some(after) if published < after)
+            || matches!(self.published_before, Some(before) if published > before)
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self::strict()
+    }
+}
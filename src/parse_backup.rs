@@ -13,6 +13,7 @@
 /// - feed=>entry=>content
 /// - feed=>entry=>id
 /// - feed=>entry=>published
+/// - feed=>entry=>thr:in-reply-to
 /// - feed=>entry=>thr:total
 /// - feed=>entry=>title
 /// - feed=>entry=>updated
@@ -21,132 +22,693 @@
 /// - feed=>title
 /// - feed=>updated
 ///
+/// The prefixes are the ones blogger uses for each namespace (see `namespaces`);
+/// elements are matched on their namespace, so other prefixes in the file don't matter.
+///
+/// A Google Takeout `feed.atom` uses the same outline, but says what each entry is
+/// with `feed=>entry=>blogger:type` and links comments to their post with
+/// `feed=>entry=>blogger:parent` instead of `kind#...` categories and `thr:in-reply-to`.
+///
 /// In other words, there are a few main entity types:
 /// feed, author, and entry.
 /// Of those, only entry corresponds to actual blog posts.
 /// However, both comments and posts are entries.
-/// get_posts figures all that out.
+/// get_posts figures all that out from each entry's `kind#...` category,
+/// and finds a comment's post from the `ref` of its `thr:in-reply-to`.
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
 use std::str::FromStr;
 
-use quick_xml::events::Event;
-use quick_xml::Reader;
-
-use crate::models::Entry;
-use crate::models::EntryKind;
+use crate::entry_reader::EntryReader;
+use crate::errors::Diagnostic;
+use crate::errors::ParseError;
+use crate::errors::ParseResult;
+use crate::models::Blog;
+use crate::models::ExportFormat;
+use crate::models::OrphanComment;
+use crate::models::Page;
+use crate::models::ParsedEntry;
 use crate::models::Post;
-use crate::xml_tools::end_tag_string;
-use crate::xml_tools::start_tag_string;
-use crate::xml_tools::string_from_bytes_text;
-use crate::xml_tools::string_from_cow;
-use crate::xml_tools::XPath;
-
-// const COMMENT_KIND: &[u8] = b"http://schemas.google.com/blogger/2008/kind#comment";
-const POST_ID_PREFIX: &[u8] = b"tag:blogger.com,1999:blog";
-const POST_KIND: &[u8] = b"http://schemas.google.com/blogger/2008/kind#post";
-const SETTINGS_KIND: &[u8] = b"http://schemas.google.com/blogger/2008/kind#settings";
-const TEMPLATE_KIND: &[u8] = b"http://schemas.google.com/blogger/2008/kind#template";
+use crate::options::ParseOptions;
+use crate::settings::BlogSettings;
+
+/// Read posts from the backup file at `file_path`.
+pub fn get_posts(file_path: &str) -> ParseResult<Vec<Post>> {
+    let file = BufReader::new(File::open(file_path)?);
+    get_posts_from_reader(file)
+}
+
+/// Read posts from a backup that is already in memory.
+pub fn get_posts_from_bytes(bytes: &[u8]) -> ParseResult<Vec<Post>> {
+    get_posts_from_reader(bytes)
+}
+
+/// Read posts from a backup held in a string.
+pub fn get_posts_from_str(xml: &str) -> ParseResult<Vec<Post>> {
+    get_posts_from_reader(xml.as_bytes())
+}
+
+/// Read posts from any buffered source, failing on the first malformed entry.
+pub fn get_posts_from_reader<B: BufRead>(source: B) -> ParseResult<Vec<Post>> {
+    let (posts, _diagnostics) = get_posts_with_options(source, &ParseOptions::strict())?;
+    Ok(posts)
+}
+
+/// Like `get_posts_from_reader`, but with options.
+/// With lenient options the problems that were worked around come back alongside the posts.
+/// Comments whose post is missing are reported as diagnostics too;
+/// use `parse_blog_with_options` to get the comments themselves.
+pub fn get_posts_with_options<B: BufRead>(
+    source: B,
+    options: &ParseOptions,
+) -> ParseResult<(Vec<Post>, Vec<Diagnostic>)> {
+    let (blog, diagnostics) = parse_blog_with_options(source, options)?;
+    Ok((blog.posts, diagnostics))
+}
+
+/// Read the whole blog from the backup file at `file_path`.
+pub fn parse_blog(file_path: &str) -> ParseResult<Blog> {
+    let file = BufReader::new(File::open(file_path)?);
+    parse_blog_from_reader(file)
+}
+
+/// Read the whole blog from any buffered source, failing on the first malformed entry.
+pub fn parse_blog_from_reader<B: BufRead>(source: B) -> ParseResult<Blog> {
+    let (blog, _diagnostics) = parse_blog_with_options(source, &ParseOptions::strict())?;
+    Ok(blog)
+}
 
 /// Logic in this function:
-/// - finds entries,
-/// - determines whether they are posts or comments, and
-/// - assigns comments to their posts
-pub fn get_posts(file_path: &str) -> Result<Vec<Post>, Box<dyn std::error::Error>> {
-    let mut buf = Vec::new();
+/// - streams entries out of the backup,
+/// - sorts them into posts, pages, comments, settings and the template,
+/// - assigns comments to their posts and pages, and
+/// - adds the blog's own details from the feed header
+pub fn parse_blog_with_options<B: BufRead>(
+    source: B,
+    options: &ParseOptions,
+) -> ParseResult<(Blog, Vec<Diagnostic>)> {
     let mut comments = Vec::new();
-    let mut entry = Entry::new();
+    let mut other_entries = Vec::new();
+    let mut pages = HashMap::new();
+    // Where each post and page ends, for the diagnostics about them.
+    let mut positions = HashMap::new();
     let mut posts = HashMap::new();
-    let mut reader = Reader::from_file(file_path)?;
-    let mut xpath = XPath::new();
-    loop {
-        match reader.read_event(&mut buf) {
-            Ok(Event::Start(ref bytes_start)) => {
-                xpath.push(start_tag_string(bytes_start)?);
+    let mut settings = Vec::new();
+    let mut template = None;
+    let mut entries = EntryReader::from_reader(source).with_options(options.to_owned());
+    while let Some(entry) = entries.next() {
+        let position = entries.position();
+        match entry? {
+            ParsedEntry::Comment(comment) => comments.push((comment, position)),
+            ParsedEntry::Page(page) => {
+                positions.insert(page.comment_post_id(), position);
+                pages.insert(page.comment_post_id(), page);
             }
-            Ok(Event::End(ref bytes_end)) => {
-                if xpath.as_string() == "feed=>entry" {
-                    match entry.kind {
-                        Some(EntryKind::Comment) => comments.push(entry.to_comment().unwrap()),
-                        Some(EntryKind::Post) => {
-                            let post = entry.to_post().unwrap();
-                            posts.insert(post.id.to_owned(), post);
-                        }
-                        _ => (),
-                    }
-                    entry.clear();
-                }
-                xpath.pop_checked(end_tag_string(bytes_end)?);
+            ParsedEntry::Post(post) => {
+                positions.insert(post.id, position);
+                posts.insert(post.id, post);
             }
-            Ok(Event::Empty(byte_start)) => {
-                for attribute in byte_start.attributes().flatten() {
-                    match attribute.value {
-                        value if value == POST_KIND => entry.kind = Some(EntryKind::Post),
-                        value if value == SETTINGS_KIND => entry.kind = Some(EntryKind::Settings),
-                        value if value == TEMPLATE_KIND => entry.kind = Some(EntryKind::Template),
-                        value if value.starts_with(POST_ID_PREFIX) => {
-                            entry.kind = Some(EntryKind::Comment);
-                            entry.post_id = Some(string_from_cow(value)?);
-                        }
-                        _value => {
-                            // let value = string_from_cow(value)?;
-                            // dbg!(value);
-                        }
-                    }
-                }
-            }
-            Ok(Event::Text(bytes_text)) => {
-                let text = Some(string_from_bytes_text(bytes_text)?);
-                match xpath.as_string().as_str() {
-                    "feed=>entry=>author=>name" => entry.author_name = text,
-                    "feed=>entry=>published" => {
-                        let text = text.unwrap();
-                        let published = parse_published(&text)?;
-                        entry.published = Some(published);
-                    }
-                    "feed=>entry=>id" => entry.id = text,
-                    "feed=>entry=>title" => entry.title = text,
-                    "feed=>entry=>content" => entry.content = text,
-                    "feed=>entry=>app:control=>app:draft" => {
-                        if text.unwrap() == "yes" {
-                            entry.draft = true;
-                            println!("This post is a draft")
-                        }
-                    }
-                    "feed=>entry" => println!("{}", text.unwrap()),
-                    _ => (),
-                }
-            }
-            Ok(Event::Eof) => break,
-            Err(e) => panic!("Error at position {}: {:?}", reader.buffer_position(), e),
-            Ok(_event) => {}
+            ParsedEntry::Settings(setting) => settings.push(setting),
+            ParsedEntry::Template(entry) => template = Some(entry),
+            ParsedEntry::Other(entry) => other_entries.push(entry),
         }
     }
-    for comment in comments {
+    let mut orphan_comments = Vec::new();
+    let mut orphan_positions = Vec::new();
+    for (comment, position) in comments {
+        if entries.skipped_posts().contains(&comment.post_id) {
+            continue;
+        }
         if let Some(post) = posts.get_mut(&comment.post_id) {
             post.comments.push(comment);
+        } else if let Some(page) = pages.get_mut(&comment.post_id) {
+            page.comments.push(comment);
         } else {
-            println!("missing post for comment {:?}", comment);
+            let reason = format!("no post or page {} in the backup", comment.post_id);
+            orphan_comments.push(OrphanComment { comment, reason });
+            orphan_positions.push(position);
         }
     }
-    let mut posts: Vec<Post> = posts.into_iter().map(|(_, post)| post).collect();
-    posts.sort_by(|a, b| a.published.cmp(&b.published));
-    Ok(posts)
+    let mut posts: Vec<Post> = posts.into_values().collect();
+    posts.sort_by_key(|post| post.published);
+    let mut pages: Vec<Page> = pages.into_values().collect();
+    pages.sort_by_key(|page| page.published);
+    let mut diagnostics = entries.take_diagnostics();
+    // Comments the options left out would make every count look wrong.
+    if options.keeps_every_comment() {
+        diagnostics.extend(
+            posts
+                .iter()
+                .filter_map(|post| comment_count_diagnostic(post, positions[&post.id])),
+        );
+        diagnostics.extend(pages.iter().filter_map(|page| {
+            page_comment_count_diagnostic(page, positions[&page.comment_post_id()])
+        }));
+    }
+    let format = entries.feed().format;
+    diagnostics.extend(
+        orphan_comments
+            .iter()
+            .zip(orphan_positions)
+            .map(|(orphan, position)| orphan_diagnostic(orphan, format, position)),
+    );
+
+    let feed = entries.feed().to_owned();
+    let blog = Blog {
+        author: feed.author,
+        format: feed.format,
+        generator: feed.generator,
+        id: feed.id.unwrap_or_default(),
+        orphan_comments,
+        other_entries,
+        pages,
+        posts,
+        settings: BlogSettings::from_settings(&settings),
+        template,
+        title: feed.title.unwrap_or_default(),
+        updated: feed.updated,
+    };
+    Ok((blog, diagnostics))
 }
 
-pub fn parse_published(
-    published: &str,
-) -> Result<chrono::DateTime<chrono::FixedOffset>, Box<dyn std::error::Error>> {
-    let dt = chrono::DateTime::from_str(published)?;
-    Ok(dt)
+/// Report a post whose `thr:total` doesn't match the comments found for it.
+/// `position` is where the post was read, see `EntryReader::position`.
+pub fn comment_count_diagnostic(post: &Post, position: usize) -> Option<Diagnostic> {
+    let (declared, found) = post.comment_count_mismatch()?;
+    Some(count_diagnostic(
+        "post",
+        post.id.to_string(),
+        position,
+        declared,
+        found,
+    ))
+}
+
+/// Report a page whose `thr:total` doesn't match the comments found for it.
+pub fn page_comment_count_diagnostic(page: &Page, position: usize) -> Option<Diagnostic> {
+    let (declared, found) = page.comment_count_mismatch()?;
+    Some(count_diagnostic(
+        "page",
+        page.id.to_string(),
+        position,
+        declared,
+        found,
+    ))
+}
+
+fn count_diagnostic(
+    kind: &str,
+    entry_id: String,
+    position: usize,
+    declared: u32,
+    found: usize,
+) -> Diagnostic {
+    Diagnostic {
+        entry_id: Some(entry_id),
+        position,
+        xpath: "feed=>entry=>thr:total".to_owned(),
+        reason: format!(
+            "{} declares {} comments but {} were found",
+            kind, declared, found
+        ),
+    }
+}
+
+/// Report a comment that couldn't be attached to a post or page.
+pub fn orphan_diagnostic(
+    orphan: &OrphanComment,
+    format: ExportFormat,
+    position: usize,
+) -> Diagnostic {
+    let xpath = match format {
+        ExportFormat::Classic => "feed=>entry=>thr:in-reply-to",
+        ExportFormat::Takeout => "feed=>entry=>blogger:parent",
+    };
+    Diagnostic {
+        entry_id: Some(orphan.comment.id.to_string()),
+        position,
+        xpath: xpath.to_owned(),
+        reason: format!("orphaned comment: {}", orphan.reason),
+    }
+}
+
+pub fn parse_published(published: &str) -> ParseResult<chrono::DateTime<chrono::FixedOffset>> {
+    chrono::DateTime::from_str(published).map_err(|source| ParseError::BadTimestamp {
+        position: 0,
+        entry_id: None,
+        value: published.to_owned(),
+        source,
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use super::get_posts;
+    use super::*;
+    use crate::models::PostStatus;
 
     #[test]
     fn test_get_posts() {
         let posts = get_posts("data/backup.xml").unwrap();
         dbg!(posts);
     }
+
+    #[test]
+    fn test_get_posts_from_bytes_and_str() {
+        let xml = std::fs::read_to_string("data/backup.xml").unwrap();
+        let from_file = get_posts("data/backup.xml").unwrap();
+        let from_bytes = get_posts_from_bytes(xml.as_bytes()).unwrap();
+        let from_str = get_posts_from_str(&xml).unwrap();
+        assert_eq!(from_file.len(), 3);
+        assert_eq!(from_bytes.len(), from_file.len());
+        assert_eq!(from_str.len(), from_file.len());
+        assert_eq!(from_str[1].title, "A crosscut sled that stays square");
+        assert_eq!(from_str[1].comments.len(), 3);
+    }
+
+    const POST_KIND_CATEGORY: &str = "<category scheme='http://schemas.google.com/g/2005#kind' term='http://schemas.google.com/blogger/2008/kind#post'/>";
+
+    #[test]
+    fn test_missing_field_is_an_error() {
+        let xml = format!(
+            "<feed><entry><id>tag:blogger.com,1999:blog-1.post-2</id>{}\
+             <published>2020-01-01T00:00:00.000-07:00</published>\
+             <updated>2020-01-01T00:00:00.000-07:00</updated>\
+             <content type='html'>Hi</content><author><name>A</name></author></entry></feed>",
+            POST_KIND_CATEGORY
+        );
+        match get_posts_from_str(&xml) {
+            Err(ParseError::MissingField {
+                entry_id, field, ..
+            }) => {
+                assert_eq!(
+                    entry_id.as_deref(),
+                    Some("tag:blogger.com,1999:blog-1.post-2")
+                );
+                assert_eq!(field, "title");
+            }
+            other => panic!("expected a missing title, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_bad_timestamp_is_an_error() {
+        let xml = format!(
+            "<feed><entry><id>tag:blogger.com,1999:blog-1.post-2</id>{}\
+             <published>last tuesday</published></entry></feed>",
+            POST_KIND_CATEGORY
+        );
+        match get_posts_from_str(&xml) {
+            Err(error @ ParseError::BadTimestamp { .. }) => {
+                assert_eq!(error.entry_id(), Some("tag:blogger.com,1999:blog-1.post-2"));
+                assert!(error.position().unwrap() > 0);
+            }
+            other => panic!("expected a bad timestamp, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_lenient_parse_reports_diagnostics() {
+        let xml = format!(
+            "<feed>\
+             <entry><id>tag:blogger.com,1999:blog-1.post-2</id>{kind}\
+             <published>2020-01-01T00:00:00.000-07:00</published>\
+             <updated>2020-01-02T00:00:00.000-07:00</updated>\
+             <content type='html'>No title here</content><author><name>A</name></author></entry>\
+             <entry><id>tag:blogger.com,1999:blog-1.post-3</id>{kind}\
+             <published>whenever</published><updated>2020-01-01T00:00:00.000-07:00</updated>\
+             <title>Bad date</title>\
+             <content type='html'>Hi</content><author><name>A</name></author></entry>\
+             </feed>",
+            kind = POST_KIND_CATEGORY
+        );
+        assert!(get_posts_from_str(&xml).is_err());
+        let (posts, diagnostics) =
+            get_posts_with_options(xml.as_bytes(), &ParseOptions::lenient()).unwrap();
+        assert_eq!(posts.len(), 1);
+        assert_eq!(posts[0].title, "");
+        assert_eq!(posts[0].content, "No title here");
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(
+            diagnostics[0].entry_id.as_deref(),
+            Some("tag:blogger.com,1999:blog-1.post-2")
+        );
+        assert!(diagnostics[0].reason.contains("title"));
+        assert_eq!(posts[0].author.name, "A");
+        assert_eq!(diagnostics[1].xpath, "feed=>entry=>published");
+        assert!(diagnostics[1].reason.starts_with("bad timestamp"));
+        assert!(!diagnostics[1].reason.contains("position"));
+        assert!(diagnostics[2].reason.contains("published"));
+        assert!(diagnostics.iter().all(|d| d.position > 0));
+    }
+
+    #[test]
+    fn test_links() {
+        let posts = get_posts("data/backup.xml").unwrap();
+        let sled = &posts[1];
+        assert_eq!(
+            sled.url(),
+            Some(
+                "https://workshopnotes.blogspot.com/2014/05/a-crosscut-sled-that-stays-square.html"
+            )
+        );
+        assert_eq!(
+            sled.permalink_path(),
+            Some("/2014/05/a-crosscut-sled-that-stays-square.html")
+        );
+        assert_eq!(sled.links.iter().filter(|l| l.rel == "replies").count(), 2);
+        assert!(sled.link("edit").is_some());
+        let comment = &sled.comments[0];
+        assert!(comment.url().unwrap().contains("showComment="));
+
+        let draft = &posts[2];
+        assert_eq!(draft.url(), None);
+        assert_eq!(draft.permalink_path(), None);
+    }
+
+    #[test]
+    fn test_updated_and_comment_counts() {
+        let posts = get_posts("data/backup.xml").unwrap();
+        let sled = &posts[1];
+        assert_eq!(sled.updated.to_rfc3339(), "2014-05-19T21:04:56.118-06:00");
+        assert_eq!(sled.declared_comment_count, Some(3));
+        assert_eq!(sled.comment_count_mismatch(), None);
+        assert!(sled.comments[0].updated >= sled.comments[0].published);
+
+        let xml = format!(
+            "<feed><entry><id>tag:blogger.com,1999:blog-1.post-2</id>{}\
+             <published>2020-01-01T00:00:00.000-07:00</published>\
+             <updated>2020-01-01T00:00:00.000-07:00</updated><title>T</title>\
+             <content type='html'>Hi</content><author><name>A</name></author>\
+             <thr:total>4</thr:total></entry></feed>",
+            POST_KIND_CATEGORY
+        );
+        let (posts, diagnostics) =
+            get_posts_with_options(xml.as_bytes(), &ParseOptions::strict()).unwrap();
+        assert_eq!(posts[0].comment_count_mismatch(), Some((4, 0)));
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].reason.contains("declares 4 comments"));
+    }
+
+    #[test]
+    fn test_page_comment_counts() {
+        let blog = parse_blog("data/backup.xml").unwrap();
+        assert_eq!(blog.pages[0].declared_comment_count, Some(1));
+        assert_eq!(blog.pages[0].comment_count_mismatch(), None);
+
+        let xml = [
+            "<feed xmlns='http://www.w3.org/2005/Atom' \
+             xmlns:thr='http://purl.org/syndication/thread/1.0'>"
+                .to_owned(),
+            mixed_entry("page-30", "page", "<thr:total>2</thr:total>"),
+            mixed_entry("post-40", "comment", &in_reply_to("30")),
+            "</feed>".to_owned(),
+        ]
+        .concat();
+        let (_, diagnostics) =
+            parse_blog_with_options(xml.as_bytes(), &ParseOptions::strict()).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].reason,
+            "page declares 2 comments but 1 were found"
+        );
+        // Reported where the page ends.
+        let page_end = xml.find("</entry>").unwrap() + "</entry>".len();
+        assert_eq!(diagnostics[0].position, page_end);
+    }
+
+    #[test]
+    fn test_comment_tree() {
+        let posts = get_posts("data/backup.xml").unwrap();
+        let sled = &posts[1];
+        let reply = sled
+            .comments
+            .iter()
+            .find(|c| c.author.name == "Dana Harris")
+            .unwrap();
+        assert_eq!(
+            reply.in_reply_to.map(|id| id.to_string()).as_deref(),
+            Some("tag:blogger.com,1999:blog-4158473287126612345.post-5519934277381020164")
+        );
+
+        let tree = sled.comment_tree();
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].comment.title, "Great write-up");
+        assert!(tree[0].replies.is_empty());
+        assert_eq!(tree[1].comment.author.name, "Marco Ruiz");
+        assert_eq!(tree[1].replies.len(), 1);
+        assert_eq!(tree[1].replies[0].comment.id, reply.id);
+    }
+
+    #[test]
+    fn test_parse_blog() {
+        let blog = parse_blog("data/backup.xml").unwrap();
+        assert_eq!(
+            blog.id,
+            "tag:blogger.com,1999:blog-4158473287126612345.archive"
+        );
+        assert_eq!(blog.blog_id().unwrap().number(), 4158473287126612345);
+        assert_eq!(blog.title, "Notes from the Workshop");
+        assert_eq!(
+            blog.updated.unwrap().to_rfc3339(),
+            "2021-11-02T09:15:44.512-07:00"
+        );
+        let generator = blog.generator.unwrap();
+        assert_eq!(generator.name, "Blogger");
+        assert_eq!(generator.version.as_deref(), Some("7.00"));
+        assert_eq!(blog.author.unwrap().name, "Dana Harris");
+        assert_eq!(blog.posts.len(), 3);
+        assert_eq!(blog.settings.raw.len(), 7);
+        assert_eq!(blog.orphan_comments.len(), 1);
+        let orphan = &blog.orphan_comments[0];
+        assert_eq!(orphan.comment.post_id.number(), 6655443322110099887);
+        assert!(orphan.reason.contains("6655443322110099887"));
+        assert!(blog.other_entries.is_empty());
+
+        assert_eq!(blog.pages.len(), 1);
+        let about = &blog.pages[0];
+        assert_eq!(about.title, "About");
+        assert_eq!(about.permalink_path(), Some("/p/about.html"));
+        assert_eq!(about.comments.len(), 1);
+        assert_eq!(about.comments[0].content, "Nice shop!");
+    }
+
+    #[test]
+    fn test_parse_takeout_blog() {
+        let blog = parse_blog("data/feed.atom").unwrap();
+        assert_eq!(blog.format, ExportFormat::Takeout);
+        assert_eq!(blog.title, "Field Notes");
+        assert_eq!(blog.posts.len(), 2);
+        assert_eq!(blog.pages.len(), 1);
+        assert_eq!(blog.pages[0].permalink_path(), Some("/p/contact.html"));
+        // Takeout names a page as the parent by its own id.
+        let page_comments = &blog.pages[0].comments;
+        assert_eq!(page_comments.len(), 1);
+        assert_eq!(page_comments[0].author.name, "Ruth Alder");
+        assert!(blog.orphan_comments.is_empty());
+
+        let counts = &blog.posts[0];
+        assert_eq!(
+            counts.permalink_path(),
+            Some("/2022/04/spring-migration-counts.html")
+        );
+        let tree = counts.comment_tree();
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].comment.author.name, "Lena Park");
+        assert_eq!(tree[0].replies.len(), 1);
+        assert_eq!(blog.posts[1].status, PostStatus::Draft);
+    }
+
+    fn mixed_entry(id: &str, kind: &str, extra: &str) -> String {
+        format!(
+            "<entry><id>tag:blogger.com,1999:blog-1.{id}</id>\
+             <published>2020-01-01T00:00:00Z</published><updated>2020-01-01T00:00:00Z</updated>\
+             <category scheme='http://schemas.google.com/g/2005#kind' \
+              term='http://schemas.google.com/blogger/2008/kind#{kind}'/>\
+             <title>{id}</title><content type='html'>Hi</content>\
+             <author><name>A</name></author>{extra}</entry>"
+        )
+    }
+
+    fn in_reply_to(post: &str) -> String {
+        format!(
+            "<thr:in-reply-to href='https://example.blogspot.com/p.html' \
+             ref='tag:blogger.com,1999:blog-1.post-{post}' \
+             source='http://www.blogger.com/feeds/1/posts/default/{post}' type='text/html'/>"
+        )
+    }
+
+    #[test]
+    fn test_comments_replies_and_pages_mixed() {
+        let reply_link = "<link rel='related' type='application/atom+xml' \
+            href='https://www.blogger.com/feeds/1/10/comments/default/20'/>";
+        // Ids of other entries in attributes mustn't make a post look like a comment.
+        let lookalike = "<gd:extendedProperty name='copied-from' \
+            value='tag:blogger.com,1999:blog-1.post-99'/>";
+        let xml = [
+            "<feed xmlns='http://www.w3.org/2005/Atom' \
+             xmlns:thr='http://purl.org/syndication/thread/1.0' \
+             xmlns:gd='http://schemas.google.com/g/2005'>"
+                .to_owned(),
+            mixed_entry("post-20", "comment", &in_reply_to("10")),
+            mixed_entry("post-10", "post", lookalike),
+            mixed_entry("page-30", "page", ""),
+            mixed_entry("post-21", "comment", &(in_reply_to("10") + reply_link)),
+            mixed_entry("post-40", "comment", &in_reply_to("30")),
+            mixed_entry("post-50", "comment", &in_reply_to("60")),
+            "</feed>".to_owned(),
+        ]
+        .concat();
+        let blog = parse_blog_from_reader(xml.as_bytes()).unwrap();
+        assert_eq!(blog.posts.len(), 1);
+        let post = &blog.posts[0];
+        assert_eq!(post.id.to_string(), "tag:blogger.com,1999:blog-1.post-10");
+        assert_eq!(post.comments.len(), 2);
+        let tree = post.comment_tree();
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].comment.title, "post-20");
+        assert_eq!(tree[0].replies[0].comment.title, "post-21");
+
+        assert_eq!(blog.pages.len(), 1);
+        assert_eq!(blog.pages[0].comments[0].title, "post-40");
+        assert_eq!(blog.orphan_comments.len(), 1);
+        assert_eq!(
+            blog.orphan_comments[0].comment.post_id.to_string(),
+            "tag:blogger.com,1999:blog-1.post-60"
+        );
+    }
+
+    #[test]
+    fn test_orphans_are_diagnostics() {
+        let file = BufReader::new(File::open("data/backup.xml").unwrap());
+        let (posts, diagnostics) = get_posts_with_options(file, &ParseOptions::strict()).unwrap();
+        assert_eq!(posts.len(), 3);
+        let orphans: Vec<&Diagnostic> = diagnostics
+            .iter()
+            .filter(|d| d.reason.starts_with("orphaned comment"))
+            .collect();
+        assert_eq!(orphans.len(), 1);
+        let id = orphans[0].entry_id.as_deref().unwrap();
+        let backup = std::fs::read_to_string("data/backup.xml").unwrap();
+        let comment_start = backup.find(id).unwrap();
+        let comment_end = comment_start + backup[comment_start..].find("</entry>").unwrap();
+        assert_eq!(orphans[0].position, comment_end + "</entry>".len());
+    }
+
+    fn titles(blog: &Blog) -> Vec<&str> {
+        blog.posts.iter().map(|post| post.title.as_str()).collect()
+    }
+
+    #[test]
+    fn test_options_leave_out_entries() {
+        let backup = std::fs::read("data/backup.xml").unwrap();
+        let options = ParseOptions::strict()
+            .include_drafts(false)
+            .include_pages(false)
+            .include_settings(false)
+            .include_templates(false);
+        let (blog, _) = parse_blog_with_options(&backup[..], &options).unwrap();
+        assert_eq!(
+            titles(&blog),
+            vec!["Hello from the garage", "A crosscut sled that stays square"]
+        );
+        assert_eq!(blog.posts[1].comments.len(), 3);
+        assert!(blog.pages.is_empty());
+        assert!(blog.settings.raw.is_empty());
+        assert!(blog.template.is_none());
+        // The page's comment goes with the page; only the real orphan is left.
+        assert_eq!(blog.orphan_comments.len(), 1);
+
+        let since = parse_published("2014-01-01T00:00:00Z").unwrap();
+        let options = ParseOptions::strict()
+            .published_after(since)
+            .deny_label("Finishing")
+            .include_comments(false);
+        let (blog, diagnostics) = parse_blog_with_options(&backup[..], &options).unwrap();
+        assert_eq!(titles(&blog), vec!["A crosscut sled that stays square"]);
+        assert!(blog.posts[0].comments.is_empty());
+        assert!(blog.pages.is_empty());
+        assert!(blog.orphan_comments.is_empty());
+        assert!(diagnostics.is_empty());
+
+        let options = ParseOptions::strict()
+            .allow_label("Shop")
+            .allow_label("Jigs");
+        let (blog, _) = parse_blog_with_options(&backup[..], &options).unwrap();
+        assert_eq!(blog.posts.len(), 2);
+    }
+
+    #[test]
+    fn test_max_content_size() {
+        let backup = std::fs::read("data/backup.xml").unwrap();
+        let options = ParseOptions::strict().max_content_size(40);
+        let (blog, diagnostics) = parse_blog_with_options(&backup[..], &options).unwrap();
+        assert_eq!(titles(&blog), vec!["Shellac, again"]);
+        assert!(blog.template.is_none());
+        assert!(blog.pages.is_empty());
+        let sled = diagnostics
+            .iter()
+            .find(|d| {
+                d.entry_id
+                    .as_deref()
+                    .unwrap_or_default()
+                    .ends_with("770012")
+            })
+            .unwrap();
+        assert_eq!(sled.reason, "content is over 40 bytes, skipped the entry");
+        assert_eq!(sled.xpath, "feed=>entry=>content");
+        // Comments on skipped posts aren't orphans.
+        assert_eq!(blog.orphan_comments.len(), 1);
+
+        let xml = [
+            "<feed xmlns='http://www.w3.org/2005/Atom'>".to_owned(),
+            mixed_entry("post-2", "post", "").replace(
+                "<content type='html'>Hi</content>",
+                "<content type='xhtml'><div xmlns='http://www.w3.org/1999/xhtml'>\
+                 <p>a long paragraph of xhtml</p></div></content>",
+            ),
+            "</feed>".to_owned(),
+        ]
+        .concat();
+        let options = ParseOptions::lenient().max_content_size(10);
+        let (posts, diagnostics) = get_posts_with_options(xml.as_bytes(), &options).unwrap();
+        assert!(posts.is_empty());
+        assert_eq!(diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn test_truncated_backup_is_an_error() {
+        let backup = std::fs::read_to_string("data/backup.xml").unwrap();
+        let cut = backup[..backup.len() * 3 / 4].rfind('\n').unwrap();
+        let truncated = &backup[..cut];
+        let error = get_posts_from_str(truncated).unwrap_err();
+        assert!(matches!(error, ParseError::UnexpectedEof { .. }));
+        assert!(matches!(
+            get_posts_with_options(truncated.as_bytes(), &ParseOptions::lenient()),
+            Err(ParseError::UnexpectedEof { .. })
+        ));
+
+        let error = get_posts_from_str("<feed><entry><id>x</id>").unwrap_err();
+        match &error {
+            ParseError::UnexpectedEof { open, .. } => assert_eq!(open, "feed=>entry"),
+            _ => panic!("unexpected error {}", error),
+        }
+        assert_eq!(error.entry_id(), Some("x"));
+    }
+
+    #[test]
+    fn test_malformed_xml_is_an_error() {
+        let result = get_posts_from_str("<feed><entry><title>Hi</entry></feed>");
+        assert!(matches!(result, Err(ParseError::Xml { .. })));
+
+        let id = "tag:blogger.com,1999:blog-1.post-2";
+        let xml = format!("<feed><entry><id>{}</id><title>Hi</x></entry></feed>", id);
+        let error = get_posts_from_str(&xml).unwrap_err();
+        assert!(matches!(error, ParseError::Xml { .. }));
+        assert_eq!(error.entry_id(), Some(id));
+        assert!(error
+            .to_string()
+            .contains(&format!("(entry {} at position", id)));
+    }
 }
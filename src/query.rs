@@ -0,0 +1,260 @@
+/// Filter, sort and page through parsed posts without writing the iterator chains by hand.
+///
+/// ```
+/// use parse_blogger_backup_xml::query::{PostQuery, SortKey, SortOrder};
+/// use parse_blogger_backup_xml::{get_posts, PostStatus};
+///
+/// let posts = get_posts("data/backup.xml").unwrap();
+/// let busiest = PostQuery::new()
+///     .status(PostStatus::Live)
+///     .min_comments(1)
+///     .sort_by(SortKey::CommentCount, SortOrder::Descending)
+///     .limit(10)
+///     .run(&posts);
+/// assert_eq!(busiest[0].title, "A crosscut sled that stays square");
+/// ```
+use std::cmp::Ordering;
+
+use chrono::DateTime;
+use chrono::FixedOffset;
+use regex::Regex;
+
+use crate::models::Post;
+use crate::models::PostStatus;
+
+/// What posts are sorted on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortKey {
+    #[default]
+    Published,
+    Updated,
+    /// Titles are compared ignoring case.
+    Title,
+    CommentCount,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+/// A search in a title or content.
+#[derive(Clone, Debug)]
+enum TextMatch {
+    /// A substring, already lowercased, to find ignoring case.
+    Contains(String),
+    Regex(Regex),
+}
+
+impl TextMatch {
+    fn matches(&self, text: &str) -> bool {
+        match self {
+            TextMatch::Contains(needle) => text.to_lowercase().contains(needle),
+            TextMatch::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+/// A set of conditions on posts, with an order and a page to return.
+/// Every condition given must hold.  Without any sort, posts come back oldest first.
+#[derive(Clone, Debug, Default)]
+pub struct PostQuery {
+    author: Option<String>,
+    content: Vec<TextMatch>,
+    labels: Vec<String>,
+    limit: Option<usize>,
+    max_comments: Option<usize>,
+    min_comments: Option<usize>,
+    offset: usize,
+    order: SortOrder,
+    published_after: Option<DateTime<FixedOffset>>,
+    published_before: Option<DateTime<FixedOffset>>,
+    sort_key: SortKey,
+    statuses: Vec<PostStatus>,
+    title: Vec<TextMatch>,
+}
+
+impl PostQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Posts published at or after this time.
+    pub fn published_after(mut self, after: DateTime<FixedOffset>) -> Self {
+        self.published_after = Some(after);
+        self
+    }
+    /// Posts published at or before this time.
+    pub fn published_before(mut self, before: DateTime<FixedOffset>) -> Self {
+        self.published_before = Some(before);
+        self
+    }
+    /// Posts with this label.  Given several times, posts must have them all.
+    pub fn label(mut self, label: &str) -> Self {
+        self.labels.push(label.to_owned());
+        self
+    }
+    /// Posts by the author with this name.
+    pub fn author(mut self, name: &str) -> Self {
+        self.author = Some(name.to_owned());
+        self
+    }
+    /// Posts with this status.  Given several times, posts may have any of them.
+    pub fn status(mut self, status: PostStatus) -> Self {
+        self.statuses.push(status);
+        self
+    }
+    /// Posts whose title contains this text, ignoring case.
+    pub fn title_contains(mut self, text: &str) -> Self {
+        self.title.push(TextMatch::Contains(text.to_lowercase()));
+        self
+    }
+    pub fn title_matches(mut self, regex: Regex) -> Self {
+        self.title.push(TextMatch::Regex(regex));
+        self
+    }
+    /// Posts whose content contains this text, ignoring case.
+    /// Html content is searched as it is, markup and all.
+    pub fn content_contains(mut self, text: &str) -> Self {
+        self.content.push(TextMatch::Contains(text.to_lowercase()));
+        self
+    }
+    pub fn content_matches(mut self, regex: Regex) -> Self {
+        self.content.push(TextMatch::Regex(regex));
+        self
+    }
+    /// Posts with at least this many comments attached.
+    pub fn min_comments(mut self, count: usize) -> Self {
+        self.min_comments = Some(count);
+        self
+    }
+    /// Posts with at most this many comments attached.
+    pub fn max_comments(mut self, count: usize) -> Self {
+        self.max_comments = Some(count);
+        self
+    }
+    pub fn sort_by(mut self, key: SortKey, order: SortOrder) -> Self {
+        self.sort_key = key;
+        self.order = order;
+        self
+    }
+    /// Skip this many matching posts.
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+    /// Return at most this many posts.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+    /// Return one page of results.  Pages are numbered from 1.
+    pub fn page(self, number: usize, per_page: usize) -> Self {
+        self.offset(number.saturating_sub(1) * per_page)
+            .limit(per_page)
+    }
+
+    /// Whether a post meets every condition.
+    pub fn matches(&self, post: &Post) -> bool {
+        let comments = post.comments.len();
+        !matches!(self.published_after, Some(after) if post.published < after)
+            && !matches!(self.published_before, Some(before) if post.published > before)
+            && self.labels.iter().all(|label| post.has_label(label))
+            && !matches!(&self.author, Some(name) if post.author.name != *name)
+            && (self.statuses.is_empty() || self.statuses.contains(&post.status))
+            && self.title.iter().all(|text| text.matches(&post.title))
+            && self.content.iter().all(|text| text.matches(&post.content))
+            && !matches!(self.min_comments, Some(min) if comments < min)
+            && !matches!(self.max_comments, Some(max) if comments > max)
+    }
+
+    /// The number of matching posts, before the offset and limit are applied.
+    pub fn count(&self, posts: &[Post]) -> usize {
+        posts.iter().filter(|post| self.matches(post)).count()
+    }
+
+    /// The matching posts, sorted, from the requested page.
+    pub fn run<'a>(&self, posts: &'a [Post]) -> Vec<&'a Post> {
+        let mut found: Vec<&Post> = posts.iter().filter(|post| self.matches(post)).collect();
+        found.sort_by(|a, b| {
+            let ordering = self.compare(a, b);
+            match self.order {
+                SortOrder::Ascending => ordering,
+                SortOrder::Descending => ordering.reverse(),
+            }
+        });
+        found
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+
+    fn compare(&self, a: &Post, b: &Post) -> Ordering {
+        match self.sort_key {
+            SortKey::Published => a.published.cmp(&b.published),
+            SortKey::Updated => a.updated.cmp(&b.updated),
+            SortKey::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+            SortKey::CommentCount => a.comments.len().cmp(&b.comments.len()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_backup::get_posts;
+    use crate::parse_backup::parse_published;
+
+    fn titles(posts: Vec<&Post>) -> Vec<&str> {
+        posts.into_iter().map(|post| post.title.as_str()).collect()
+    }
+
+    #[test]
+    fn test_post_query() {
+        let posts = get_posts("data/backup.xml").unwrap();
+        assert_eq!(
+            titles(PostQuery::new().run(&posts)),
+            vec![
+                "Hello from the garage",
+                "A crosscut sled that stays square",
+                "Shellac, again"
+            ]
+        );
+        let by_title = PostQuery::new().sort_by(SortKey::Title, SortOrder::Descending);
+        assert_eq!(
+            titles(by_title.clone().run(&posts)),
+            vec![
+                "Shellac, again",
+                "Hello from the garage",
+                "A crosscut sled that stays square"
+            ]
+        );
+        assert_eq!(
+            titles(by_title.page(2, 2).run(&posts)),
+            vec!["A crosscut sled that stays square"]
+        );
+
+        let since = parse_published("2014-01-01T00:00:00Z").unwrap();
+        let recent = PostQuery::new().published_after(since);
+        assert_eq!(recent.count(&posts), 2);
+        assert_eq!(
+            titles(recent.status(PostStatus::Live).run(&posts)),
+            vec!["A crosscut sled that stays square"]
+        );
+
+        let query = PostQuery::new()
+            .label("Jigs")
+            .author("Dana Harris")
+            .title_contains("CROSSCUT")
+            .content_matches(Regex::new(r"Five-cut|four-cut").unwrap())
+            .min_comments(3);
+        assert_eq!(query.count(&posts), 1);
+        assert_eq!(query.max_comments(2).count(&posts), 0);
+        let query = PostQuery::new().title_matches(Regex::new("^(Hello|Shellac)").unwrap());
+        assert_eq!(query.count(&posts), 2);
+        assert_eq!(PostQuery::new().content_contains("bench").count(&posts), 1);
+    }
+}
@@ -0,0 +1,138 @@
+/// Typed access to the blog's `kind#settings` entries.
+///
+/// Each settings entry holds one `BLOG_*` key and its value.
+/// The common ones are pulled out into fields,
+/// and every key, common or not, is kept in `raw`.
+use std::collections::BTreeMap;
+
+use crate::models::Setting;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CommentModeration {
+    Disabled,
+    Always,
+    /// Only posts older than a number of days need approval.
+    AfterDays(u32),
+    Other(String),
+}
+
+impl CommentModeration {
+    pub fn from_value(value: &str) -> Self {
+        match value {
+            "DISABLED" | "NEVER" => CommentModeration::Disabled,
+            "ALWAYS" | "ENABLED" => CommentModeration::Always,
+            other => {
+                let days = other
+                    .strip_prefix("AFTER_")
+                    .and_then(|rest| rest.strip_suffix("_DAYS"))
+                    .and_then(|days| days.parse().ok());
+                match days {
+                    Some(days) => CommentModeration::AfterDays(days),
+                    None => CommentModeration::Other(other.to_owned()),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlogSettings {
+    /// `BLOG_ADULT_CONTENT`
+    pub adult_content: Option<bool>,
+    /// `BLOG_COMMENT_ACCESS`: who may comment, e.g. `ANYONE` or `REGISTERED`.
+    pub comment_access: Option<String>,
+    /// `BLOG_COMMENT_MODERATION`
+    pub comment_moderation: Option<CommentModeration>,
+    /// `BLOG_DESCRIPTION`
+    pub description: Option<String>,
+    /// `BLOG_LOCALE`
+    pub locale: Option<String>,
+    /// `BLOG_NAME`
+    pub name: Option<String>,
+    /// `BLOG_MAX_NUM`, unless `BLOG_MAX_UNIT` says it counts days rather than posts.
+    pub posts_per_page: Option<u32>,
+    /// `BLOG_TIME_ZONE`, an IANA name such as `America/Denver`.
+    pub time_zone: Option<String>,
+    /// Every setting by key, including the ones above.
+    pub raw: BTreeMap<String, String>,
+}
+
+impl BlogSettings {
+    pub fn from_settings(settings: &[Setting]) -> Self {
+        let raw: BTreeMap<String, String> = settings
+            .iter()
+            .map(|setting| (setting.name.to_owned(), setting.value.to_owned()))
+            .collect();
+        let text = |key: &str| raw.get(key).filter(|v| !v.is_empty()).cloned();
+        let flag = |key: &str| match raw.get(key).map(|v| v.to_ascii_lowercase()) {
+            Some(v) if v == "true" || v == "yes" => Some(true),
+            Some(v) if v == "false" || v == "no" => Some(false),
+            _ => None,
+        };
+        let counts_days = raw.get("BLOG_MAX_UNIT").map(String::as_str) == Some("DAYS");
+        let posts_per_page = if counts_days {
+            None
+        } else {
+            raw.get("BLOG_MAX_NUM").and_then(|n| n.trim().parse().ok())
+        };
+        Self {
+            adult_content: flag("BLOG_ADULT_CONTENT"),
+            comment_access: text("BLOG_COMMENT_ACCESS"),
+            comment_moderation: text("BLOG_COMMENT_MODERATION")
+                .map(|v| CommentModeration::from_value(&v)),
+            description: text("BLOG_DESCRIPTION"),
+            locale: text("BLOG_LOCALE"),
+            name: text("BLOG_NAME"),
+            posts_per_page,
+            time_zone: text("BLOG_TIME_ZONE"),
+            raw,
+        }
+    }
+
+    /// The raw value of any setting, e.g. `get("BLOG_DATE_FORMAT")`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.raw.get(key).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_backup::parse_blog;
+
+    #[test]
+    fn test_blog_settings() {
+        let settings = parse_blog("data/backup.xml").unwrap().settings;
+        assert_eq!(settings.name.as_deref(), Some("Notes from the Workshop"));
+        assert_eq!(
+            settings.description.as_deref(),
+            Some("Projects, jigs and the occasional disaster.")
+        );
+        assert_eq!(settings.time_zone.as_deref(), Some("America/Denver"));
+        assert_eq!(settings.locale.as_deref(), Some("en"));
+        assert_eq!(settings.posts_per_page, Some(7));
+        assert_eq!(
+            settings.comment_moderation,
+            Some(CommentModeration::AfterDays(14))
+        );
+        assert_eq!(settings.adult_content, None);
+        assert_eq!(settings.get("BLOG_SHOW_COMMENT_FORM"), Some("EMBEDDED"));
+        assert_eq!(settings.raw.len(), 7);
+    }
+
+    #[test]
+    fn test_comment_moderation_values() {
+        assert_eq!(
+            CommentModeration::from_value("DISABLED"),
+            CommentModeration::Disabled
+        );
+        assert_eq!(
+            CommentModeration::from_value("ALWAYS"),
+            CommentModeration::Always
+        );
+        assert_eq!(
+            CommentModeration::from_value("SOMETIMES"),
+            CommentModeration::Other("SOMETIMES".to_owned())
+        );
+    }
+}
@@ -0,0 +1,55 @@
+/// Helpers for sorting posts by status: live, draft, scheduled or soft-trashed.
+use std::collections::BTreeMap;
+
+use crate::models::Post;
+use crate::models::PostStatus;
+
+/// Posts with the given status.
+pub fn with_status(posts: &[Post], status: PostStatus) -> impl Iterator<Item = &Post> {
+    posts.iter().filter(move |post| post.status == status)
+}
+
+/// Posts whose status is any of `statuses`.
+pub fn with_any_status<'a>(
+    posts: &'a [Post],
+    statuses: &'a [PostStatus],
+) -> impl Iterator<Item = &'a Post> {
+    posts
+        .iter()
+        .filter(move |post| statuses.contains(&post.status))
+}
+
+/// The number of posts with each status that occurs.
+pub fn status_counts(posts: &[Post]) -> BTreeMap<PostStatus, usize> {
+    let mut counts = BTreeMap::new();
+    for post in posts {
+        *counts.entry(post.status).or_insert(0) += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_backup::get_posts;
+
+    #[test]
+    fn test_status() {
+        let posts = get_posts("data/backup.xml").unwrap();
+        let drafts: Vec<&str> = with_status(&posts, PostStatus::Draft)
+            .map(|post| post.title.as_str())
+            .collect();
+        assert_eq!(drafts, vec!["Shellac, again"]);
+        assert_eq!(with_status(&posts, PostStatus::Live).count(), 2);
+        let statuses = [PostStatus::Live, PostStatus::Draft];
+        assert_eq!(with_any_status(&posts, &statuses).count(), 3);
+
+        let counts = status_counts(&posts);
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&PostStatus::Live], 2);
+        assert_eq!(
+            PostStatus::from_name("soft-trashed"),
+            Some(PostStatus::SoftTrashed)
+        );
+    }
+}
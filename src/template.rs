@@ -0,0 +1,249 @@
+/// Break a Blogger layout template down into its sections and widgets.
+///
+/// A template is an xhtml document where `<b:section>` elements hold
+/// `<b:widget>` elements, and each widget keeps its data in
+/// `<b:widget-settings>`:
+///
+/// ```xml
+/// <b:section id='sidebar-right-1'>
+///   <b:widget id='LinkList1' title='Friends' type='LinkList'>
+///     <b:widget-settings>
+///       <b:widget-setting name='link-0'>https://example.org</b:widget-setting>
+///       <b:widget-setting name='text-0'>Example</b:widget-setting>
+///     </b:widget-settings>
+///   </b:widget>
+/// </b:section>
+/// ```
+use std::collections::BTreeMap;
+
+use quick_xml::events::BytesStart;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use crate::errors::ParseError;
+use crate::errors::ParseResult;
+use crate::models::Template;
+use crate::xml_tools::attribute_value;
+use crate::xml_tools::string_from_bytes_text;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WidgetKind {
+    Attribution,
+    Blog,
+    BlogArchive,
+    Feed,
+    Header,
+    Html,
+    Image,
+    Label,
+    LinkList,
+    PopularPosts,
+    Profile,
+    Text,
+    Other(String),
+}
+
+impl WidgetKind {
+    pub fn from_type(widget_type: &str) -> Self {
+        match widget_type {
+            "Attribution" => WidgetKind::Attribution,
+            "Blog" => WidgetKind::Blog,
+            "BlogArchive" => WidgetKind::BlogArchive,
+            "Feed" => WidgetKind::Feed,
+            "Header" => WidgetKind::Header,
+            "HTML" => WidgetKind::Html,
+            "Image" => WidgetKind::Image,
+            "Label" => WidgetKind::Label,
+            "LinkList" => WidgetKind::LinkList,
+            "PopularPosts" => WidgetKind::PopularPosts,
+            "Profile" => WidgetKind::Profile,
+            "Text" => WidgetKind::Text,
+            other => WidgetKind::Other(other.to_owned()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Widget {
+    pub id: String,
+    pub kind: WidgetKind,
+    pub settings: BTreeMap<String, String>,
+    pub title: Option<String>,
+}
+
+impl Widget {
+    /// The markup of an HTML or Text widget.
+    pub fn content(&self) -> Option<&str> {
+        self.settings.get("content").map(String::as_str)
+    }
+
+    /// The `(text, url)` pairs of a LinkList widget, in their numbered order.
+    pub fn links(&self) -> Vec<(String, String)> {
+        let mut links: Vec<(u32, String, String)> = self
+            .settings
+            .iter()
+            .filter_map(|(key, url)| {
+                let index = key.strip_prefix("link-")?;
+                let text = self.settings.get(&format!("text-{}", index))?;
+                Some((index.parse().ok()?, text.to_owned(), url.to_owned()))
+            })
+            .collect();
+        links.sort();
+        links
+            .into_iter()
+            .map(|(_index, text, url)| (text, url))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Section {
+    pub class: Option<String>,
+    pub id: String,
+    pub widgets: Vec<Widget>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Layout {
+    pub sections: Vec<Section>,
+}
+
+impl Layout {
+    pub fn widgets(&self) -> impl Iterator<Item = &Widget> {
+        self.sections.iter().flat_map(|section| &section.widgets)
+    }
+
+    pub fn widget(&self, id: &str) -> Option<&Widget> {
+        self.widgets().find(|widget| widget.id == id)
+    }
+}
+
+impl Template {
+    /// Parse the template's sections and widgets.
+    pub fn layout(&self) -> ParseResult<Layout> {
+        parse_layout(&self.content)
+    }
+}
+
+pub fn parse_layout(template: &str) -> ParseResult<Layout> {
+    let mut reader = Reader::from_str(template);
+    // Templates mix in html, so don't insist that every tag is closed properly.
+    reader.check_end_names(false);
+    let mut buf = Vec::new();
+    let mut layout = Layout::default();
+    let mut widget: Option<Widget> = None;
+    let mut setting: Option<(String, String)> = None;
+    loop {
+        let event = reader.read_event(&mut buf);
+        let position = reader.buffer_position();
+        let at = |error: ParseError| error.located(position, None);
+        match event {
+            Ok(Event::Start(ref bytes_start)) => match bytes_start.name() {
+                b"b:section" => layout.sections.push(section(bytes_start).map_err(at)?),
+                b"b:widget" => widget = Some(new_widget(bytes_start).map_err(at)?),
+                b"b:widget-setting" => {
+                    let name = attribute_value(bytes_start, b"name").map_err(at)?;
+                    setting = name.map(|name| (name, String::new()));
+                }
+                _ => (),
+            },
+            Ok(Event::Empty(ref bytes_start)) => match bytes_start.name() {
+                b"b:section" => layout.sections.push(section(bytes_start).map_err(at)?),
+                b"b:widget" => {
+                    let widget = new_widget(bytes_start).map_err(at)?;
+                    add_widget(&mut layout, widget);
+                }
+                _ => (),
+            },
+            // quick-xml hands CDATA over escaped, so both unescape the same way.
+            Ok(Event::Text(bytes_text)) | Ok(Event::CData(bytes_text)) => {
+                if let Some((_name, value)) = setting.as_mut() {
+                    value.push_str(&string_from_bytes_text(bytes_text).map_err(at)?);
+                }
+            }
+            Ok(Event::End(ref bytes_end)) => match bytes_end.name() {
+                b"b:widget-setting" => {
+                    if let (Some((name, value)), Some(widget)) = (setting.take(), widget.as_mut()) {
+                        widget.settings.insert(name, value);
+                    }
+                }
+                b"b:widget" => {
+                    if let Some(widget) = widget.take() {
+                        add_widget(&mut layout, widget);
+                    }
+                }
+                _ => (),
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(at(e.into())),
+            Ok(_event) => (),
+        }
+        buf.clear();
+    }
+    Ok(layout)
+}
+
+fn section(bytes_start: &BytesStart) -> ParseResult<Section> {
+    Ok(Section {
+        class: attribute_value(bytes_start, b"class")?,
+        id: attribute_value(bytes_start, b"id")?.unwrap_or_default(),
+        widgets: vec![],
+    })
+}
+
+fn new_widget(bytes_start: &BytesStart) -> ParseResult<Widget> {
+    let widget_type = attribute_value(bytes_start, b"type")?.unwrap_or_default();
+    Ok(Widget {
+        id: attribute_value(bytes_start, b"id")?.unwrap_or_default(),
+        kind: WidgetKind::from_type(&widget_type),
+        settings: BTreeMap::new(),
+        title: attribute_value(bytes_start, b"title")?,
+    })
+}
+
+/// Widgets belong to the section they appear in.
+fn add_widget(layout: &mut Layout, widget: Widget) {
+    if let Some(section) = layout.sections.last_mut() {
+        section.widgets.push(widget);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_backup::parse_blog;
+
+    #[test]
+    fn test_layout() {
+        let blog = parse_blog("data/backup.xml").unwrap();
+        let layout = blog.template.unwrap().layout().unwrap();
+        let ids: Vec<&str> = layout.sections.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["main", "sidebar-right-1"]);
+        assert_eq!(layout.widgets().count(), 4);
+
+        let about = layout.widget("HTML1").unwrap();
+        assert_eq!(about.kind, WidgetKind::Html);
+        assert_eq!(about.title.as_deref(), Some("About the Workshop"));
+        assert_eq!(about.content(), Some("<p>Woodworking, mostly.</p>"));
+
+        let friends = layout.widget("LinkList1").unwrap();
+        assert_eq!(friends.kind, WidgetKind::LinkList);
+        assert_eq!(
+            friends.links(),
+            vec![
+                (
+                    "Tool Library".to_owned(),
+                    "https://example.org/tools".to_owned()
+                ),
+                (
+                    "Lumber Yard".to_owned(),
+                    "https://example.org/lumber".to_owned()
+                ),
+            ]
+        );
+
+        let archive = layout.widget("BlogArchive1").unwrap();
+        assert_eq!(archive.kind, WidgetKind::BlogArchive);
+        assert!(archive.settings.is_empty());
+    }
+}
@@ -30,13 +30,11 @@ where
     if let Some(parent) = file_path.parent() {
         fs::create_dir_all(parent)?;
     }
-    match fs::remove_file(file_path) {
-        Ok(_) => (),
-        Err(_) => (),
-    };
+    let _ = fs::remove_file(file_path);
     let mut file = fs::OpenOptions::new()
         .create(true)
         .write(true)
+        .truncate(true)
         .open(file_path)?;
 
     file.write_all(text.as_bytes())?;
@@ -2,32 +2,52 @@
 ///
 /// - Functions to convert different references and byte slices into strings.
 /// - An XPath struct with a nice string representation that you can push and pop to and from.
+/// - A QName struct for element names resolved against their namespace.
 /// - Functions to selectively print out different aspects of an xml file.
 use quick_xml::events::{BytesEnd, BytesStart, BytesText};
 use quick_xml::{events::Event, Reader};
 use std::borrow::Cow;
 use std::collections::HashSet;
 use std::fmt;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
 
-pub fn string_from_bytes_text(bytes_text: BytesText) -> Result<String, Box<dyn std::error::Error>> {
+use crate::errors::ParseError;
+use crate::errors::ParseResult;
+use crate::namespaces;
+
+pub fn string_from_bytes_text(bytes_text: BytesText) -> ParseResult<String> {
     let bytes = bytes_text.unescaped()?.into_owned();
     Ok(String::from_utf8(bytes)?)
 }
 
-pub fn start_tag_string(bytes_start: &BytesStart) -> Result<String, Box<dyn std::error::Error>> {
+pub fn start_tag_string(bytes_start: &BytesStart) -> ParseResult<String> {
     let tag = bytes_start.name();
     let tag = tag.to_owned();
     let tag = String::from_utf8(tag)?;
     Ok(tag)
 }
-pub fn end_tag_string(bytes_end: &BytesEnd) -> Result<String, Box<dyn std::error::Error>> {
+pub fn end_tag_string(bytes_end: &BytesEnd) -> ParseResult<String> {
     let tag = bytes_end.name();
     let tag = tag.to_owned();
     let tag = String::from_utf8(tag)?;
     Ok(tag)
 }
 
-pub fn string_from_cow(cow: Cow<[u8]>) -> Result<String, Box<dyn std::error::Error>> {
+/// The unescaped value of the attribute named `key`, if the element has one.
+pub fn attribute_value(bytes_start: &BytesStart, key: &[u8]) -> ParseResult<Option<String>> {
+    for attribute in bytes_start.attributes() {
+        let attribute = attribute?;
+        if attribute.key == key {
+            let value = attribute.unescaped_value()?;
+            return Ok(Some(string_from_cow(value)?));
+        }
+    }
+    Ok(None)
+}
+
+pub fn string_from_cow(cow: Cow<[u8]>) -> ParseResult<String> {
     let string = match cow {
         Cow::Owned(internal) => String::from_utf8(internal)?,
         Cow::Borrowed(internal) => String::from_utf8(internal.to_owned())?,
@@ -35,40 +55,119 @@ pub fn string_from_cow(cow: Cow<[u8]>) -> Result<String, Box<dyn std::error::Err
     Ok(string)
 }
 
-struct Stringable(String);
+/// An element name resolved against the namespaces in scope: `(namespace uri, local name)`.
+///
+/// It displays with the prefix blogger uses for the namespace, e.g. `thr:total`,
+/// whatever prefix the document itself declared.
+/// Names in an unknown namespace display as `{uri}local`,
+/// and names whose prefix was never declared are kept as written.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct QName {
+    pub namespace: Option<String>,
+    pub local: String,
+}
 
-impl fmt::Display for Stringable {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
+impl QName {
+    /// `name` is the element name as written, with its prefix if it has one.
+    pub fn new(namespace: Option<&[u8]>, name: &[u8]) -> ParseResult<Self> {
+        let local = match namespace {
+            Some(_) => name.rsplit(|b| *b == b':').next().unwrap_or(name),
+            None => name,
+        };
+        Ok(Self {
+            namespace: namespace
+                .map(|uri| String::from_utf8(uri.to_owned()))
+                .transpose()?,
+            local: String::from_utf8(local.to_owned())?,
+        })
+    }
+    pub fn is(&self, namespace: &str, local: &str) -> bool {
+        self.namespace.as_deref() == Some(namespace) && self.local == local
     }
 }
 
-impl From<&BytesStart<'_>> for Stringable {
-    fn from(start: &BytesStart) -> Self {
-        let tag = start.name();
-        let tag = tag.to_owned();
-        let tag = String::from_utf8(tag).expect("Tag not in utf8");
-        Self(tag)
+impl From<String> for QName {
+    fn from(local: String) -> Self {
+        Self {
+            namespace: None,
+            local,
+        }
     }
 }
 
-pub struct XPath(Vec<String>);
+impl fmt::Display for QName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let uri = match &self.namespace {
+            Some(uri) => uri,
+            None => return write!(f, "{}", self.local),
+        };
+        match namespaces::prefix(uri) {
+            Some("") => write!(f, "{}", self.local),
+            Some(prefix) => write!(f, "{}:{}", prefix, self.local),
+            None => write!(f, "{{{}}}{}", uri, self.local),
+        }
+    }
+}
+
+/// Read the next event, resolving the names of start, end and empty elements.
+/// `namespace_buf` holds the declarations in scope and must be kept between calls.
+pub fn read_namespaced_event<'b, B: BufRead>(
+    reader: &mut Reader<B>,
+    buf: &'b mut Vec<u8>,
+    namespace_buf: &mut Vec<u8>,
+) -> ParseResult<(Option<QName>, Event<'b>)> {
+    let (namespace, event) = reader.read_namespaced_event(buf, namespace_buf)?;
+    let name = match &event {
+        Event::Start(e) | Event::Empty(e) => Some(QName::new(namespace, e.name())?),
+        Event::End(e) => Some(QName::new(namespace, e.name())?),
+        _ => None,
+    };
+    Ok((name, event))
+}
+
+pub struct XPath(Vec<QName>);
 
 impl XPath {
     pub fn new() -> Self {
         Self(vec![])
     }
-    pub fn push(&mut self, tag: String) {
-        self.0.push(tag);
+    pub fn push(&mut self, tag: impl Into<QName>) {
+        self.0.push(tag.into());
+    }
+    pub fn len(&self) -> usize {
+        self.0.len()
     }
-    pub fn pop(&mut self) -> Option<String> {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    pub fn pop(&mut self) -> Option<QName> {
         self.0.pop()
     }
-    pub fn pop_checked(&mut self, tag: String) {
-        assert_eq!(self.pop().expect("can't end without starting."), tag);
+    /// The innermost open element.
+    pub fn last(&self) -> Option<&QName> {
+        self.0.last()
+    }
+    /// Pop the last tag, making sure that it is the one being closed.
+    pub fn pop_checked(&mut self, tag: impl Into<QName>) -> ParseResult<()> {
+        let tag = tag.into();
+        match self.pop() {
+            Some(expected) if expected == tag => Ok(()),
+            Some(expected) => Err(ParseError::MismatchedTag {
+                position: 0,
+                entry_id: None,
+                expected: expected.to_string(),
+                found: tag.to_string(),
+            }),
+            None => Err(ParseError::UnexpectedEnd {
+                position: 0,
+                entry_id: None,
+                found: tag.to_string(),
+            }),
+        }
     }
     pub fn as_string(&self) -> String {
-        self.0.join("=>")
+        let names: Vec<String> = self.0.iter().map(QName::to_string).collect();
+        names.join("=>")
     }
 }
 
@@ -90,35 +189,60 @@ impl fmt::Display for XPath {
     }
 }
 
+/// Attach the reader's current position to an error.
+fn at<B: BufRead>(reader: &Reader<B>) -> impl Fn(ParseError) -> ParseError {
+    let position = reader.buffer_position();
+    move |error| error.located(position, None)
+}
+
+fn open(path: &str) -> ParseResult<BufReader<File>> {
+    Ok(BufReader::new(File::open(path)?))
+}
+
 /// Get a list of all tags in an xml file.
-pub fn tag_names(path: &str) -> Result<HashSet<String>, Box<dyn std::error::Error>> {
-    let mut reader = Reader::from_file(path)?;
+pub fn tag_names(path: &str) -> ParseResult<HashSet<String>> {
+    tag_names_from_reader(open(path)?)
+}
+
+/// Get a list of all tags in an xml document.
+pub fn tag_names_from_reader<B: BufRead>(source: B) -> ParseResult<HashSet<String>> {
+    let mut reader = Reader::from_reader(source);
     let mut buf = Vec::new();
     let mut tag_names: HashSet<String> = HashSet::new();
     loop {
         match reader.read_event(&mut buf) {
             Ok(Event::Start(ref e)) => {
-                let tag = start_tag_string(e)?;
+                let tag = start_tag_string(e).map_err(at(&reader))?;
                 tag_names.insert(tag);
             }
             Ok(Event::Eof) => break,
-            Err(e) => panic!("Error at position {}: {:?}", reader.buffer_position(), e),
+            Err(e) => return Err(at(&reader)(e.into())),
             _ => (),
         }
     }
     Ok(tag_names)
 }
 
+/// Get a list of all text children of all elements in your xml file.
+pub fn all_text(path: &str) -> ParseResult<Vec<String>> {
+    all_text_from_reader(open(path)?)
+}
+
 /// Get a list of all text children of all elements in your xml document.
-pub fn all_text(path: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    let mut reader = Reader::from_file(path)?;
+pub fn all_text_from_reader<B: BufRead>(source: B) -> ParseResult<Vec<String>> {
+    let mut reader = Reader::from_reader(source);
     let mut buf = Vec::new();
     let mut txt = Vec::new();
     loop {
         match reader.read_event(&mut buf) {
-            Ok(Event::Text(e)) => txt.push(e.unescape_and_decode(&reader).unwrap()),
+            Ok(Event::Text(e)) => {
+                let text = e
+                    .unescape_and_decode(&reader)
+                    .map_err(|e| at(&reader)(e.into()))?;
+                txt.push(text);
+            }
             Ok(Event::Eof) => break,
-            Err(e) => panic!("Error at position {}: {:?}", reader.buffer_position(), e),
+            Err(e) => return Err(at(&reader)(e.into())),
             _ => (),
         }
     }
@@ -126,21 +250,25 @@ pub fn all_text(path: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
 }
 
 /// List all xml paths (as a set, where siblings have the same path if they have the same element type).
-pub fn paths(path: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let mut reader = Reader::from_file(path)?;
+pub fn paths(path: &str) -> ParseResult<()> {
+    paths_from_reader(open(path)?)
+}
+
+/// Like `paths`, but for any buffered source.
+pub fn paths_from_reader<B: BufRead>(source: B) -> ParseResult<()> {
+    let mut reader = Reader::from_reader(source);
     let mut xpath: XPath = XPath::new();
     let mut buf = Vec::new();
+    let mut namespace_buf = Vec::new();
     let mut xpath_strings = HashSet::new();
     loop {
-        match reader.read_event(&mut buf) {
-            Ok(Event::Start(ref e)) => {
-                xpath.push(start_tag_string(e)?);
-            }
-            Ok(Event::End(ref e)) => {
-                xpath.pop_checked(end_tag_string(e)?);
+        match read_namespaced_event(&mut reader, &mut buf, &mut namespace_buf) {
+            Ok((Some(name), Event::Start(_))) => xpath.push(name),
+            Ok((Some(name), Event::End(_))) => {
+                xpath.pop_checked(name).map_err(at(&reader))?;
             }
-            Ok(Event::Eof) => break,
-            Err(e) => panic!("Error at position {}: {:?}", reader.buffer_position(), e),
+            Ok((_, Event::Eof)) => break,
+            Err(e) => return Err(at(&reader)(e)),
             _ => (),
         }
         xpath_strings.insert(xpath.as_string());
@@ -154,23 +282,30 @@ pub fn paths(path: &str) -> Result<(), Box<dyn std::error::Error>> {
 }
 
 /// All attributes in all the elements
-pub fn all_attributes(file_path: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    let mut reader = Reader::from_file(file_path)?;
+pub fn all_attributes(file_path: &str) -> ParseResult<Vec<String>> {
+    all_attributes_from_reader(open(file_path)?)
+}
+
+/// Like `all_attributes`, but for any buffered source.
+pub fn all_attributes_from_reader<B: BufRead>(source: B) -> ParseResult<Vec<String>> {
+    let mut reader = Reader::from_reader(source);
     let mut xpath = XPath::new();
     let mut buf = Vec::new();
+    let mut namespace_buf = Vec::new();
     let mut attributes = HashSet::new();
     loop {
-        match reader.read_event(&mut buf) {
-            Ok(Event::Start(ref e)) => {
-                xpath.push(start_tag_string(e)?);
+        match read_namespaced_event(&mut reader, &mut buf, &mut namespace_buf) {
+            Ok((Some(name), Event::Start(ref e))) => {
+                xpath.push(name);
                 for attr in e.attributes() {
-                    let attr_string = format!("{:?}", attr.unwrap());
+                    let attr = attr.map_err(|e| at(&reader)(e.into()))?;
+                    let attr_string = format!("{:?}", attr);
                     attributes.insert(attr_string);
                 }
             }
-            Ok(Event::End(ref e)) => xpath.pop_checked(end_tag_string(e)?),
-            Ok(Event::Eof) => break,
-            Err(e) => panic!("Error at position {}: {:?}", reader.buffer_position(), e),
+            Ok((Some(name), Event::End(_))) => xpath.pop_checked(name).map_err(at(&reader))?,
+            Ok((_, Event::Eof)) => break,
+            Err(e) => return Err(at(&reader)(e)),
             Ok(_event) => {}
         }
     }
@@ -183,36 +318,42 @@ pub fn all_attributes(file_path: &str) -> Result<Vec<String>, Box<dyn std::error
 }
 
 /// Print out all events found under a specific xpath leaf type.
-pub fn path_contents(
-    file_path: &str,
+pub fn path_contents(file_path: &str, x_path: &str, first: u32, last: u32) -> ParseResult<()> {
+    path_contents_from_reader(open(file_path)?, x_path, first, last)
+}
+
+/// Like `path_contents`, but for any buffered source.
+pub fn path_contents_from_reader<B: BufRead>(
+    source: B,
     x_path: &str,
     first: u32,
     last: u32,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut reader = Reader::from_file(file_path)?;
+) -> ParseResult<()> {
+    let mut reader = Reader::from_reader(source);
     let mut xpath = XPath::new();
     let mut xpath_string = "".to_owned();
     let mut buf = Vec::new();
+    let mut namespace_buf = Vec::new();
     let mut index = 0;
     loop {
-        match reader.read_event(&mut buf) {
-            Ok(Event::Start(ref e)) => {
-                xpath.push(start_tag_string(e)?);
+        match read_namespaced_event(&mut reader, &mut buf, &mut namespace_buf) {
+            Ok((Some(name), Event::Start(_))) => {
+                xpath.push(name);
                 xpath_string = xpath.as_string();
             }
-            Ok(Event::End(ref e)) => {
+            Ok((Some(name), Event::End(_))) => {
                 if x_path == xpath_string {
                     index += 1;
                     if first <= index && index <= last {
                         println!()
                     };
                 }
-                xpath.pop_checked(end_tag_string(e)?);
+                xpath.pop_checked(name).map_err(at(&reader))?;
                 xpath_string = xpath.as_string();
             }
-            Ok(Event::Eof) => break,
-            Err(e) => panic!("Error at position {}: {:?}", reader.buffer_position(), e),
-            Ok(event) => {
+            Ok((_, Event::Eof)) => break,
+            Err(e) => return Err(at(&reader)(e)),
+            Ok((_, event)) => {
                 if x_path == xpath_string && first <= index && index <= last {
                     println!("{:?}", event);
                 }
@@ -280,4 +421,54 @@ mod tests {
         dbg!(tags);
         Ok(())
     }
+    #[test]
+    fn mismatched_tags_are_errors() {
+        let mut xpath = XPath::new();
+        xpath.push("feed".to_owned());
+        xpath.push("entry".to_owned());
+        assert!(matches!(
+            xpath.pop_checked("feed".to_owned()),
+            Err(ParseError::MismatchedTag { .. })
+        ));
+        assert!(xpath.pop_checked("feed".to_owned()).is_ok());
+        assert!(matches!(
+            xpath.pop_checked("feed".to_owned()),
+            Err(ParseError::UnexpectedEnd { .. })
+        ));
+    }
+    #[test]
+    fn prefixes_are_resolved() -> ParseResult<()> {
+        let xml = "<a:feed xmlns:a='http://www.w3.org/2005/Atom' \
+            xmlns:t='http://purl.org/syndication/thread/1.0'>\
+            <a:entry><t:total>1</t:total><total xmlns='http://purl.org/syndication/thread/1.0'/>\
+            <x:thing/></a:entry></a:feed>";
+        let mut reader = Reader::from_str(xml);
+        let mut buf = Vec::new();
+        let mut namespace_buf = Vec::new();
+        let mut names = Vec::new();
+        loop {
+            match read_namespaced_event(&mut reader, &mut buf, &mut namespace_buf)? {
+                (Some(name), Event::Start(_)) | (Some(name), Event::Empty(_)) => names.push(name),
+                (_, Event::Eof) => break,
+                _ => (),
+            }
+        }
+        let names: Vec<String> = names.iter().map(QName::to_string).collect();
+        assert_eq!(
+            names,
+            ["feed", "entry", "thr:total", "thr:total", "x:thing"]
+        );
+        Ok(())
+    }
+    #[test]
+    fn tag_names_from_str() -> Result<(), Box<dyn std::error::Error>> {
+        let xml = "<feed><entry><title>Hi</title></entry></feed>";
+        let tags = tag_names_from_reader(xml.as_bytes())?;
+        let expected: HashSet<String> = ["feed", "entry", "title"]
+            .iter()
+            .map(|tag| tag.to_string())
+            .collect();
+        assert_eq!(tags, expected);
+        Ok(())
+    }
 }